@@ -1,8 +1,17 @@
 use crate::{
     core::mongo::{
-        clear_connection, connect_server, mongodb_collection, MongoConnections, MongoData,
-        PaginatedResult,
+        clear_connection, close_cursor, connect_server, export_uri, list_databases,
+        mongodb_aggregate, mongodb_collection, next_batch, open_cursor, resolve_read_only,
+        AggregateParams, CollectionParams, CursorBatch, CursorParams, DatabaseInfo,
+        MongoConnections, MongoData, PaginatedResult,
     },
+    core::mongo_explain::{mongodb_explain, ExplainParams, ExplainResult},
+    core::mongo_index::{create_index, drop_index, hide_index, list_indexes, IndexInfo},
+    core::mongo_write::{
+        delete_many, delete_one, insert_many, insert_one, replace_one, update_many, update_one,
+        ReplaceParams, UpdateParams, WriteResult,
+    },
+    core::uri::{parse_uri, ConnectionProfile},
     error::Error,
     utils::{crypt::*, io_op::*},
     MONGO_DATA_FILE, SSH_KEY_FILE,
@@ -10,80 +19,238 @@ use crate::{
 use mongodb::bson::Document;
 use tauri::{AppHandle, State};
 
+// 所有连接相关的命令都以 `server` 传入前台保存的连接配置
+// 只读设置以后台保存的连接配置为准，执行前先经过 `resolve_read_only`
+
 #[tauri::command]
 pub async fn mongo_connect_server(
-    id: String,
-    r#type: String,
-    auth_method: String,
-    ssh_host: String,
-    ssh_port: u16,
-    ssh_username: String,
-    mongo_host: String,
-    mongo_port: u16,
-    mongo_username: String,
-    db_name: String,
-    app_handle: AppHandle,
-    connections: State<'_, MongoConnections>, //manage自动注入，'_匿名生命周期自动推断
+    server: MongoData,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
 ) -> Result<Vec<Document>, Error> {
-    return connect_server(
-        &MongoData::new(
-            id,
-            r#type,
-            auth_method,
-            ssh_host,
-            ssh_port,
-            ssh_username,
-            mongo_host,
-            mongo_port,
-            mongo_username,
-            db_name,
-        ),
-        connections,
-        app_handle,
-    )
-    .await;
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    connect_server(&server, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_list_databases(
+    server: MongoData,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<Vec<DatabaseInfo>, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    list_databases(&server, connections, app_handle).await
 }
 
 #[tauri::command]
 pub async fn mongo_collection(
-    id: String,
-    r#type: String,
-    auth_method: String,
-    ssh_host: String,
-    ssh_port: u16,
-    ssh_username: String,
-    mongo_host: String,
-    mongo_port: u16,
-    mongo_username: String,
-    db_name: String,
-    collection_name: String,
-    page: Option<u32>,
-    page_size: Option<u32>,
-    query: String,
+    server: MongoData,
+    params: CollectionParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<PaginatedResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    mongodb_collection(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_open_cursor(
+    server: MongoData,
+    params: CursorParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<CursorBatch, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    open_cursor(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_next_batch(
+    cursor_id: String,
+    batch_size: Option<u32>,
+    connections: State<'_, MongoConnections>,
+) -> Result<CursorBatch, Error> {
+    return next_batch(cursor_id, batch_size, connections).await;
+}
+
+#[tauri::command]
+pub async fn mongo_close_cursor(
+    cursor_id: String,
+    connections: State<'_, MongoConnections>,
+) -> Result<(), Error> {
+    return close_cursor(cursor_id, connections).await;
+}
+
+#[tauri::command]
+pub async fn mongo_aggregate(
+    server: MongoData,
+    params: AggregateParams,
     app_handle: AppHandle,
     connections: State<'_, MongoConnections>,
 ) -> Result<PaginatedResult, Error> {
-    return mongodb_collection(
-        &MongoData::new(
-            id,
-            r#type,
-            auth_method,
-            ssh_host,
-            ssh_port,
-            ssh_username,
-            mongo_host,
-            mongo_port,
-            mongo_username,
-            db_name,
-        ),
-        collection_name,
-        page,
-        page_size,
-        query,
-        connections,
-        app_handle,
-    )
-    .await;
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    mongodb_aggregate(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_insert_one(
+    server: MongoData,
+    collection_name: String,
+    document: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    insert_one(&server, collection_name, document, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_insert_many(
+    server: MongoData,
+    collection_name: String,
+    documents: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    insert_many(&server, collection_name, documents, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_update_one(
+    server: MongoData,
+    params: UpdateParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    update_one(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_update_many(
+    server: MongoData,
+    params: UpdateParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    update_many(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_replace_one(
+    server: MongoData,
+    params: ReplaceParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    replace_one(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_delete_one(
+    server: MongoData,
+    collection_name: String,
+    filter: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    delete_one(&server, collection_name, filter, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_delete_many(
+    server: MongoData,
+    collection_name: String,
+    filter: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<WriteResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    delete_many(&server, collection_name, filter, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_list_indexes(
+    server: MongoData,
+    collection_name: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<Vec<IndexInfo>, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    list_indexes(&server, collection_name, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_create_index(
+    server: MongoData,
+    collection_name: String,
+    keys: String,
+    index_options: Option<String>,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<String, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    create_index(&server, collection_name, keys, index_options, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_drop_index(
+    server: MongoData,
+    collection_name: String,
+    index_name: String,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<(), Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    drop_index(&server, collection_name, index_name, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_hide_index(
+    server: MongoData,
+    collection_name: String,
+    index_name: String,
+    hidden: bool,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<(), Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    hide_index(&server, collection_name, index_name, hidden, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_explain(
+    server: MongoData,
+    params: ExplainParams,
+    app_handle: AppHandle,
+    connections: State<'_, MongoConnections>,
+) -> Result<ExplainResult, Error> {
+    let server = resolve_read_only(server, &connections, app_handle.clone()).await?;
+    mongodb_explain(&server, params, connections, app_handle).await
+}
+
+#[tauri::command]
+pub async fn mongo_parse_uri(uri: String) -> Result<ConnectionProfile, Error> {
+    Ok(parse_uri(&uri)?)
+}
+
+// 当前构建是否支持不校验 TLS 主机名，默认的 rustls 后端不支持，前台据此隐藏该选项
+#[tauri::command]
+pub async fn mongo_supports_invalid_hostnames() -> bool {
+    cfg!(feature = "openssl-tls")
+}
+
+#[tauri::command]
+pub async fn mongo_export_uri(
+    server: MongoData,
+    include_password: Option<bool>,
+    app_handle: AppHandle,
+) -> Result<String, Error> {
+    Ok(export_uri(&server, include_password.unwrap_or(false), app_handle)?)
 }
 
 #[tauri::command]
@@ -107,6 +274,8 @@ pub async fn mongo_data_encrypt(
 #[tauri::command]
 pub async fn mongo_delete_encrypt_data(skey: &str, app_handle: AppHandle) -> Result<(), Error> {
     delete_value(skey, app_handle.clone(), MONGO_DATA_FILE)?;
-    delete_value(skey, app_handle, SSH_KEY_FILE)?;
+    delete_value(skey, app_handle.clone(), SSH_KEY_FILE)?;
+    // 多跳时其余各跳的公钥以 `{id}:{序号}` 保存
+    delete_prefixed_values(&format!("{}:", skey), app_handle, SSH_KEY_FILE)?;
     Ok(())
 }
@@ -0,0 +1,129 @@
+use anyhow::anyhow;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use mongodb::bson::{doc, Bson, Document};
+
+// 键集分页（keyset pagination）
+// 按排序键记录上一页最后一条文档的位置，下一页从该位置之后继续查询，
+// 避免 skip 在大集合上越翻越慢，也不会因为翻页期间的数据变化而重复或遗漏。
+// `$gt` / `$lt` 只比较同类型的值，排序字段混有多种类型（如数字和字符串）时，
+// 与上一页最后一个值类型不同的文档可能被跳过，此时应改用 skip 分页。
+
+/// 补全排序规则，以 `_id` 作为最后的排序键保证顺序唯一
+pub fn keyset_sort(sort: Option<Document>) -> Document {
+    let mut sort = sort.unwrap_or_default();
+    if !sort.contains_key("_id") {
+        sort.insert("_id", 1);
+    }
+    sort
+}
+
+/// 确保投影中包含排序键，否则无法从结果中取得续页位置
+pub fn keyset_projection(projection: Option<Document>, sort: &Document) -> Option<Document> {
+    let mut projection = projection?;
+    let inclusive = projection
+        .iter()
+        .any(|(k, v)| k != "_id" && is_truthy(v));
+    for key in sort.keys() {
+        if inclusive {
+            projection.insert(key.clone(), 1);
+        } else {
+            projection.remove(key);
+        }
+    }
+    Some(projection)
+}
+
+/// 根据排序规则和当前页最后一条文档生成不透明的续页令牌
+pub fn encode_token(sort: &Document, last: &Document) -> anyhow::Result<String> {
+    let values: Vec<Bson> = sort
+        .keys()
+        .map(|key| get_path(last, key).cloned().unwrap_or(Bson::Null))
+        .collect();
+    let token = doc! { "s": sort.clone(), "v": values };
+    let mut bytes = Vec::new();
+    token.to_writer(&mut bytes)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// 解析续页令牌，生成“位于上一页之后”的过滤条件
+/// - 令牌中的排序规则必须与本次查询一致
+pub fn decode_token(token: &str, sort: &Document) -> anyhow::Result<Document> {
+    let bytes = URL_SAFE_NO_PAD
+        .decode(token.trim())
+        .map_err(|_| anyhow!("invalid continuation token"))?;
+    let token = Document::from_reader(&bytes[..]).map_err(|_| anyhow!("invalid continuation token"))?;
+    let token_sort = token.get_document("s").map_err(|_| anyhow!("invalid continuation token"))?;
+    let values = token.get_array("v").map_err(|_| anyhow!("invalid continuation token"))?;
+    if token_sort != sort || values.len() != sort.len() {
+        return Err(anyhow!("continuation token does not match the current sort"));
+    }
+
+    // (k0 > v0) or (k0 = v0 and k1 > v1) or ...
+    // 缺失的排序字段记为 null，null 排在所有值之前（降序时排在最后）：
+    // - 升序时 null 之后即不为 null 的值，非 null 之后不会再有 null
+    // - 降序时非 null 之后还有全部 null，null 之后没有更靠后的值，省略该分支
+    let keys: Vec<&String> = sort.keys().collect();
+    let mut branches = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let key = key.as_str();
+        let descending = is_descending(sort.get(key));
+        let mut branch = Document::new();
+        for (prev_key, prev_value) in keys.iter().zip(values).take(i) {
+            branch.insert(prev_key.as_str(), prev_value.clone());
+        }
+        match (&values[i], descending) {
+            (Bson::Null, true) => continue,
+            (Bson::Null, false) => branch.insert(key, doc! { "$ne": Bson::Null }),
+            (value, true) => branch.insert(
+                "$or",
+                vec![doc! { key: { "$lt": value.clone() } }, doc! { key: Bson::Null }],
+            ),
+            (value, false) => branch.insert(key, doc! { "$gt": value.clone() }),
+        };
+        branches.push(Bson::Document(branch));
+    }
+    if branches.is_empty() {
+        // 已经是最后一页
+        return Ok(doc! { "$expr": false });
+    }
+    Ok(doc! { "$or": branches })
+}
+
+/// 将用户的过滤条件与续页条件合并
+pub fn merge_filter(filter: Document, after: Document) -> Document {
+    if filter.is_empty() {
+        after
+    } else {
+        doc! { "$and": [filter, after] }
+    }
+}
+
+// 按点路径取值，如 `a.b.c`
+fn get_path<'a>(doc: &'a Document, path: &str) -> Option<&'a Bson> {
+    let mut parts = path.split('.');
+    let mut value = doc.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_document()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn is_descending(direction: Option<&Bson>) -> bool {
+    match direction {
+        Some(Bson::Int32(n)) => *n < 0,
+        Some(Bson::Int64(n)) => *n < 0,
+        Some(Bson::Double(n)) => *n < 0.0,
+        _ => false,
+    }
+}
+
+fn is_truthy(value: &Bson) -> bool {
+    match value {
+        Bson::Int32(n) => *n != 0,
+        Bson::Int64(n) => *n != 0,
+        Bson::Double(n) => *n != 0.0,
+        Bson::Boolean(b) => *b,
+        // 表达式、$slice 等视为包含
+        _ => true,
+    }
+}
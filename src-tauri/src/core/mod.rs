@@ -1,3 +1,11 @@
+pub mod keyset;
 pub mod mongo;
+pub mod mongo_explain;
+pub mod mongo_index;
+pub mod mongo_write;
 pub mod server_key;
+pub mod socks;
+pub mod ssh_client;
 pub mod ssh_mongo;
+pub mod tls;
+pub mod uri;
@@ -1,29 +1,56 @@
+use super::keyset::*;
 use super::ssh_mongo::*;
+use super::tls::create_client;
+use super::uri::{ConnectionOptions, ConnectionProfile, MongoAuthMethod};
 use crate::{
     error::Error,
-    utils::{crypt::*, q2b::*},
+    utils::{b2q::*, crypt::*, q2b::*},
     MONGO_DATA_FILE,
 };
-use futures_util::stream::TryStreamExt;
-use json5;
-use log::info;
+use futures_util::stream::{StreamExt, TryStreamExt};
+use log::{info, warn};
 use mongodb::{
-    bson::{self, doc, to_document, Document},
-    options::{ClientOptions, FindOptions},
-    Client, Database,
+    bson::{self, doc, oid::ObjectId, Bson, Document},
+    error::ErrorKind,
+    options::{
+        AggregateOptions, Collation, CountOptions, EstimatedDocumentCountOptions,
+        FindOptions, Hint,
+    },
+    results::{CollectionSpecification, CollectionType},
+    Client, Collection, Cursor, Database,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use tauri::{AppHandle, State};
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::{Mutex, RwLock};
 
-// mongodb 连接配置
-#[derive(Default)]
+// 限制计数模式下默认的计数上限
+const DEFAULT_COUNT_LIMIT: u64 = 10000;
+
+// 连接时同时获取统计信息的集合数量上限
+const STATS_CONCURRENCY: usize = 8;
+
+// 游标空闲超过该时长后关闭，需明显短于 MongoDB 默认的 10 分钟游标超时，
+// 避免本地认为游标仍然有效而服务端已经将其关闭
+const CURSOR_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+// 服务端游标不存在的错误代码
+const CURSOR_NOT_FOUND_CODE: i32 = 43;
+
+// 定期清理空闲游标的间隔
+const CURSOR_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// mongodb 连接配置，直接由前台保存的连接配置反序列化，其余字段忽略
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MongoData {
     id: String,
+    #[serde(rename = "type")]
     connect_type: String,
-    mongo_auth_method: String,
+    #[serde(rename = "authMethod")]
+    mongo_auth_method: MongoAuthMethod,
     ssh_host: String,
     ssh_port: u16,
     ssh_username: String,
@@ -31,135 +58,439 @@ pub struct MongoData {
     mongo_port: u16,
     mongo_username: String,
     db_name: String,
+    // 只读连接禁止任何写操作
+    #[serde(default)]
+    read_only: bool,
+    // 当前浏览的数据库，未指定时使用连接配置中的 db_name，认证仍使用 db_name
+    #[serde(default)]
+    database: Option<String>,
+    #[serde(default)]
+    options: ConnectionOptions,
+}
+
+// 查询选项，与查询条件使用相同的语法
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryOptions {
+    projection: Option<String>,
+    sort: Option<String>,
+    collation: Option<String>,
+    hint: Option<String>,
+    max_time_ms: Option<u64>,
+}
+
+// 集合分页查询参数
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionParams {
+    pub collection_name: String,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    // 为真或传入 continuation 时使用键集分页
+    pub keyset: Option<bool>,
+    pub continuation: Option<String>,
+    pub query: String,
+    #[serde(flatten)]
+    pub options: QueryOptions,
+    pub count_mode: Option<CountMode>,
+    pub count_limit: Option<u64>,
+    pub output_format: Option<OutputFormat>,
+}
+
+// 游标查询参数
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorParams {
+    pub collection_name: String,
+    pub query: String,
+    #[serde(flatten)]
+    pub options: QueryOptions,
+    pub batch_size: Option<u32>,
+    pub output_format: Option<OutputFormat>,
+}
+
+// 聚合查询参数
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateParams {
+    pub collection_name: String,
+    pub pipeline: String,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub allow_disk_use: Option<bool>,
+    pub count_mode: Option<CountMode>,
+    pub count_limit: Option<u64>,
+    pub output_format: Option<OutputFormat>,
+}
+
+// 解析后的查询选项
+#[derive(Default)]
+pub struct ParsedQueryOptions {
+    pub projection: Option<Document>,
+    pub sort: Option<Document>,
+    pub collation: Option<Collation>,
+    pub hint: Option<Hint>,
+    pub max_time: Option<Duration>,
+}
+
+// 查询结果中文档的输出格式
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    // 规范扩展 JSON，保留全部类型信息
+    Canonical,
+    // 宽松扩展 JSON，数字和日期更易读
+    #[default]
+    Relaxed,
+    // mongosh 风格字符串，可直接粘贴回查询
+    Shell,
+}
+
+// 总数统计方式
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum CountMode {
+    // count_documents 精确计数，未建索引的条件会全表扫描
+    #[default]
+    Exact,
+    // 空条件时使用集合元数据估算，有条件时退化为 Capped
+    Estimated,
+    // 最多数到上限，超出时返回上限并标记为不精确（如 10000+）
+    Capped,
+    // 不统计总数
+    Skip,
 }
 
 // 查询结果返回
 #[derive(Serialize, Deserialize)]
 pub struct PaginatedResult {
     documents: Vec<Value>,
+    format: OutputFormat,
     total_count: u64,
+    // 总数是否精确，估算、超出上限或跳过统计时为 false
+    total_exact: bool,
+    // 实际使用的计数方式
+    count_mode: CountMode,
     page: u32,
     page_size: u32,
     total_pages: u64,
+    // 键集分页时用于获取下一页的令牌，没有更多数据时为空
+    continuation: Option<String>,
+}
+
+// 数据库列表返回
+#[derive(Serialize, Deserialize)]
+pub struct DatabaseInfo {
+    name: String,
+    size_on_disk: u64,
+    empty: bool,
+}
+
+// 游标批次返回
+#[derive(Serialize, Deserialize)]
+pub struct CursorBatch {
+    // 游标已耗尽时为空，无需再调用 next_batch
+    cursor_id: Option<String>,
+    documents: Vec<Value>,
+    format: OutputFormat,
+}
+
+// 连接池中保存的服务端游标
+struct LiveCursor {
+    connection_id: String,
+    cursor: Cursor<Document>,
+    format: OutputFormat,
+    last_used: Instant,
+}
+
+// 连接池中的连接
+struct PooledConnection {
+    client: Client,
+    ssh_tunnel_manager: Option<SshTunnelManager>,
+    // 建立连接时确定的只读设置，之后的调用不能更改
+    read_only: bool,
 }
 
 // 连接池
 #[derive(Default)]
 pub struct MongoConnections {
     // 用HashMap存储不同id对应的连接，并使用读写锁保护数据结构
-    connections: RwLock<HashMap<String, (Client, Database, Option<SshTunnelManager>)>>,
+    connections: RwLock<HashMap<String, PooledConnection>>,
+    // 按游标 id 保存仍在滚动的查询结果
+    cursors: Mutex<HashMap<String, LiveCursor>>,
 }
 
 impl MongoData {
+    // 转换为连接串导出使用的连接配置
+    fn profile(&self, mongo_password: String) -> ConnectionProfile {
+        ConnectionProfile {
+            connect_type: self.connect_type.clone(),
+            auth_method: self.mongo_auth_method,
+            mongo_host: self.mongo_host.clone(),
+            mongo_port: self.mongo_port,
+            mongo_username: self.mongo_username.clone(),
+            mongo_password,
+            db_name: self.db_name.clone(),
+            options: self.options.clone(),
+            tls_certificate_key: String::new(),
+            tls_ca_certificate: String::new(),
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// 以保存的连接配置中的只读设置为准，覆盖调用方传入的值
+    /// - 保存的连接配置中没有 `readOnly` 时视为可写
+    pub fn apply_saved_profile(&mut self, saved: &Value) {
+        self.read_only = saved["readOnly"].as_bool().unwrap_or(false);
+    }
+
+    /// 当前浏览的数据库名称
+    pub fn current_database(&self) -> &str {
+        match self.database.as_deref() {
+            Some(database) if !database.is_empty() => database,
+            _ => &self.db_name,
+        }
+    }
+
+    /// 只读连接拒绝写操作，所有写入路径都应先经过此检查
+    pub fn ensure_writable(&self, operation: &str) -> Result<(), Error> {
+        if self.read_only {
+            return Err(Error::ReadOnly(operation.to_string()));
+        }
+        Ok(())
+    }
+
+    /// 检查数据库命令是否允许在当前连接上执行
+    /// - 只读连接仅放行 `READ_ONLY_COMMANDS` 中的只读命令，其余管理命令一律拒绝
+    /// - 带 `$out` / `$merge` 的 aggregate 命令同样视为写操作
+    pub fn ensure_command_allowed(&self, command: &Document) -> Result<(), Error> {
+        if !self.read_only {
+            return Ok(());
+        }
+        let name = command.keys().next().map(String::as_str).unwrap_or_default();
+        if !READ_ONLY_COMMANDS.iter().any(|c| c.eq_ignore_ascii_case(name)) {
+            return self.ensure_writable(&format!("command '{}'", name));
+        }
+        if name == "aggregate" {
+            if let Ok(pipeline) = command.get_array("pipeline") {
+                let stages: Vec<Document> = pipeline
+                    .iter()
+                    .filter_map(|stage| stage.as_document().cloned())
+                    .collect();
+                self.ensure_pipeline_allowed(&stages)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 只读连接拒绝以 `$out` / `$merge` 写出结果的聚合管道
+    pub fn ensure_pipeline_allowed(&self, pipeline: &[Document]) -> Result<(), Error> {
+        match pipeline.iter().find_map(output_stage) {
+            Some(stage) => self.ensure_writable(&format!("{} stage", stage)),
+            None => Ok(()),
+        }
+    }
+}
+
+// 只读连接允许执行的数据库命令
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "aggregate",
+    "buildInfo",
+    "collStats",
+    "connectionStatus",
+    "count",
+    "dataSize",
+    "dbStats",
+    "distinct",
+    "explain",
+    "find",
+    "getMore",
+    "hello",
+    "hostInfo",
+    "isMaster",
+    "killCursors",
+    "listCollections",
+    "listDatabases",
+    "listIndexes",
+    "ping",
+    "serverStatus",
+];
+
+// 管道中写出结果的阶段名称
+fn output_stage(stage: &Document) -> Option<&'static str> {
+    if stage.contains_key("$out") {
+        Some("$out")
+    } else if stage.contains_key("$merge") {
+        Some("$merge")
+    } else {
+        None
+    }
+}
+
+impl QueryOptions {
     pub fn new(
-        id: String,
-        connect_type: String,
-        mongo_auth_method: String,
-        ssh_host: String,
-        ssh_port: u16,
-        ssh_username: String,
-        mongo_host: String,
-        mongo_port: u16,
-        mongo_username: String,
-        db_name: String,
+        projection: Option<String>,
+        sort: Option<String>,
+        collation: Option<String>,
+        hint: Option<String>,
+        max_time_ms: Option<u64>,
     ) -> Self {
-        MongoData {
-            id,
-            connect_type,
-            mongo_auth_method,
-            ssh_host,
-            ssh_port,
-            ssh_username,
-            mongo_host,
-            mongo_port,
-            mongo_username,
-            db_name,
+        QueryOptions {
+            projection,
+            sort,
+            collation,
+            hint,
+            max_time_ms,
         }
     }
+
+    /// 解析各项选项，任何一项语法错误都直接返回
+    pub fn parse(&self) -> Result<ParsedQueryOptions, Error> {
+        let collation = match optional_document(self.collation.as_deref())? {
+            Some(doc) => Some(bson::from_document::<Collation>(doc)?),
+            None => None,
+        };
+        Ok(ParsedQueryOptions {
+            projection: optional_document(self.projection.as_deref())?,
+            sort: optional_document(self.sort.as_deref())?,
+            collation,
+            hint: parse_hint(self.hint.as_deref())?,
+            max_time: self.max_time_ms.map(Duration::from_millis),
+        })
+    }
 }
 
-/// 格式化 MongoDB 连接 URI
-/// - 如果认证方式是 `userpass`，则从加密文件中获取密码并拼接 URI
-/// - 否则直接拼接 URI
-pub fn format_uri(
-	mongo_data: &MongoData, 
-	app_handle: AppHandle
+/// 将连接配置导出为连接串
+/// - 如果认证方式需要密码，且 `include_password` 为真，则从加密文件中获取密码
+/// - 认证机制和连接选项（多节点、副本集、authSource、TLS 等）一并写入连接串
+pub fn export_uri(
+    mongo_data: &MongoData,
+    include_password: bool,
+    app_handle: AppHandle,
 ) -> anyhow::Result<String> {
-    if mongo_data.mongo_auth_method == "userpass" {
-        let decrypt_server_str = decrypt(&mongo_data.id, app_handle, MONGO_DATA_FILE)?;
-        let decrypt_server: Value = serde_json::from_str(&decrypt_server_str)?;
-        let mongo_password = decrypt_server["mongoPassword"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("mongoPassword is empty"))?;
+    let mongo_password = if include_password && mongo_data.mongo_auth_method.uses_password() {
+        stored_mongo_password(mongo_data, app_handle)?
+    } else {
+        String::new()
+    };
+    Ok(mongo_data.profile(mongo_password).to_uri(include_password))
+}
 
-        return Ok(format!(
-            "mongodb://{}:{}@{}:{}/{}",
-            mongo_data.mongo_username,
-            mongo_password,
-            mongo_data.mongo_host,
-            mongo_data.mongo_port,
-            mongo_data.db_name
-        ));
+// 从加密文件中读取 MongoDB 密码
+fn stored_mongo_password(mongo_data: &MongoData, app_handle: AppHandle) -> anyhow::Result<String> {
+    let decrypt_server = stored_secrets(mongo_data, app_handle)?;
+    Ok(decrypt_server["mongoPassword"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("mongoPassword is empty"))?
+        .to_string())
+}
+
+// 从加密文件中读取密码、客户端证书等认证信息
+fn stored_secrets(mongo_data: &MongoData, app_handle: AppHandle) -> anyhow::Result<Value> {
+    saved_profile(&mongo_data.id, app_handle)?
+        .ok_or_else(|| anyhow::anyhow!("saved connection profile not found"))
+}
+
+// 读取加密保存的完整连接配置，没有保存时返回 None
+fn saved_profile(id: &str, app_handle: AppHandle) -> anyhow::Result<Option<Value>> {
+    let decrypt_server_str = decrypt(id, app_handle, MONGO_DATA_FILE)?;
+    if decrypt_server_str.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&decrypt_server_str)?))
+}
+
+/// 确定连接的只读设置，不信任调用方传入的 `readOnly`
+/// - 连接池中已有连接时，使用建立连接时固定下来的设置
+/// - 否则以加密保存的连接配置为准，只有早期未保存加密配置的连接才使用传入的值
+pub async fn resolve_read_only(
+    mut mongo_data: MongoData,
+    connections: &MongoConnections,
+    app_handle: AppHandle,
+) -> Result<MongoData, Error> {
+    if let Some(connection) = connections.connections.read().await.get(&mongo_data.id) {
+        mongo_data.read_only = connection.read_only;
+        return Ok(mongo_data);
+    }
+    if let Some(saved) = saved_profile(&mongo_data.id, app_handle)? {
+        mongo_data.apply_saved_profile(&saved);
     }
-    Ok(format!(
-        "mongodb://{}:{}/{}",
-        mongo_data.mongo_host, mongo_data.mongo_port, mongo_data.db_name
-    ))
+    Ok(mongo_data)
 }
 
 /// 获取 MongoDB 连接
 /// - 先从连接池中查找是否存在对应 ID 的连接
-/// - 如果不存在，则根据连接类型（SSH 或直接连接）创建新连接并存入连接池
-async fn get_mongodb_connection<'a>(
+/// - 如果不存在，则从加密文件中读取密码、客户端证书，根据连接类型（SSH 或直接连接）创建新连接并存入连接池
+/// - 认证信息通过 `Credential` 设置，不拼接到连接串中
+/// - 新连接固定 `mongo_data` 的只读设置，调用方需先经过 `resolve_read_only`
+async fn get_mongodb_connection(
     mongo_data: &MongoData,
-    uri: &str,
-    connections: &'a MongoConnections,
+    connections: &MongoConnections,
     app_handle: AppHandle,
 ) -> Result<(Client, Database), Error> {
     // 先尝试读取已存在的连接
     let connections_read = connections.connections.read().await;
-    if let Some((client, db, _ssh_tunnel_manager)) = connections_read.get(&mongo_data.id) {
-        return Ok((client.clone(), db.clone()));
+    // 同一连接可以浏览不同的数据库，共用连接池中的 Client
+    if let Some(connection) = connections_read.get(&mongo_data.id) {
+        let db = connection.client.database(mongo_data.current_database());
+        return Ok((connection.client.clone(), db));
     }
 
     // 释放读锁，准备获取写锁
     drop(connections_read);
 
-    let (client, db, ssh_tunnel_manager) = if mongo_data.connect_type == "ssh" {
-        let decrypt_server_str = decrypt(&mongo_data.id, app_handle.clone(), MONGO_DATA_FILE)?;
-        let decrypt_server: Value = serde_json::from_str(&decrypt_server_str)?;
-        let mongo_password = if mongo_data.mongo_auth_method == "userpass" {
-            decrypt_server["mongoPassword"]
-                .as_str()
-                .ok_or_else(|| anyhow::anyhow!("mongoPassword is empty"))?
-        } else {
-            ""
-        };
-
-        let ssh_password = decrypt_server["sshPassword"]
+    let decrypt_server = if mongo_data.connect_type == "ssh"
+        || mongo_data.mongo_auth_method.uses_password()
+        || mongo_data.options.tls_client_certificate
+        || mongo_data.options.tls_ca_certificate
+    {
+        stored_secrets(mongo_data, app_handle.clone())?
+    } else {
+        Value::Null
+    };
+    let mongo_password = if mongo_data.mongo_auth_method.uses_password() {
+        decrypt_server["mongoPassword"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("sshPassword is empty"))?;
+            .ok_or_else(|| anyhow::anyhow!("mongoPassword is empty"))?
+    } else {
+        ""
+    };
+    let mut profile = mongo_data.profile(mongo_password.to_string());
+    if mongo_data.options.tls_client_certificate {
+        profile.tls_certificate_key = decrypt_server["tlsCertificateKey"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("tlsCertificateKey is empty"))?
+            .to_string();
+    }
+    if mongo_data.options.tls_ca_certificate {
+        profile.tls_ca_certificate = decrypt_server["tlsCaCertificate"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("tlsCaCertificate is empty"))?
+            .to_string();
+    }
 
-        ssh_tunnel_to_mongodb(
-            &mongo_data.id,
-            &mongo_data.mongo_auth_method,
+    let (client, _db, ssh_tunnel_manager) = if mongo_data.connect_type == "ssh" {
+        let hops = SshHop::chain_from_secrets(
             &mongo_data.ssh_host,
             mongo_data.ssh_port,
             &mongo_data.ssh_username,
-            &ssh_password,
-            // &format!("{}:{}", mongo_data.mongo_host, 27018),
-            &format!("{}:{}", mongo_data.mongo_host, mongo_data.mongo_port),
-            &mongo_data.mongo_username,
-            &mongo_password,
-            &mongo_data.db_name,
-            app_handle,
-        )
-        .await?
+            &decrypt_server,
+        )?;
+
+        ssh_tunnel_to_mongodb(&mongo_data.id, &hops, &profile, app_handle)
+            .await?
     } else {
         // 创建新连接
-        let mut client_options = ClientOptions::parse(uri).await?;
+        let mut client_options = profile.client_options().await?;
         client_options.server_selection_timeout = Some(std::time::Duration::from_secs(15)); // 服务器连接超时时间
-        let dclient = Client::with_options(client_options)?;
+        let dclient = create_client(
+            client_options,
+            &profile.tls_certificate_key,
+            &profile.tls_ca_certificate,
+        )?;
         let ddb = dclient.database(&mongo_data.db_name);
         (dclient, ddb, None)
     };
@@ -168,138 +499,611 @@ async fn get_mongodb_connection<'a>(
     let mut connections_write = connections.connections.write().await;
     connections_write.insert(
         mongo_data.id.to_string(),
-        (client.clone(), db.clone(), ssh_tunnel_manager),
+        PooledConnection {
+            client: client.clone(),
+            ssh_tunnel_manager,
+            read_only: mongo_data.read_only,
+        },
     );
 
+    let db = client.database(mongo_data.current_database());
     Ok((client, db))
 }
 
+/// 列出当前用户有权限访问的数据库
+/// - 使用 `authorizedDatabases`，没有 `listDatabases` 权限的用户也能看到自己的数据库
+pub async fn list_databases(
+    mongo_data: &MongoData,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<Vec<DatabaseInfo>, Error> {
+    let (client, _db) = get_mongodb_connection(mongo_data, &connections, app_handle).await?;
+
+    let databases = client.list_databases().authorized_databases(true).await?;
+    Ok(databases
+        .into_iter()
+        .map(|database| DatabaseInfo {
+            name: database.name,
+            size_on_disk: database.size_on_disk,
+            empty: database.empty,
+        })
+        .collect())
+}
+
+/// 获取当前连接的数据库，连接不存在时自动创建
+pub async fn get_database(
+    mongo_data: &MongoData,
+    connections: &MongoConnections,
+    app_handle: AppHandle,
+) -> Result<Database, Error> {
+    let (_client, db) = get_mongodb_connection(mongo_data, connections, app_handle).await?;
+    Ok(db)
+}
+
+/// 获取指定集合，连接不存在时自动创建
+pub async fn get_collection(
+    mongo_data: &MongoData,
+    collection_name: &str,
+    connections: &MongoConnections,
+    app_handle: AppHandle,
+) -> Result<Collection<Document>, Error> {
+    let db = get_database(mongo_data, connections, app_handle).await?;
+    Ok(db.collection(collection_name))
+}
+
+/// 执行数据库命令，只读连接上的管理命令会被拒绝
+pub async fn run_command(
+    mongo_data: &MongoData,
+    db: &Database,
+    command: Document,
+) -> Result<Document, Error> {
+    mongo_data.ensure_command_allowed(&command)?;
+    Ok(db.run_command(command).await?)
+}
+
 /// 连接 MongoDB 服务器并获取所有集合的统计信息
 pub async fn connect_server(
     mongo_data: &MongoData,
     connections: State<'_, MongoConnections>, // 显式指定生命周期
     app_handle: AppHandle,
 ) -> Result<Vec<Document>, Error> {
-    let uri = format_uri(mongo_data, app_handle.clone())?;
-
     // 从连接池获取或创建连接
-    let (_client, db) = get_mongodb_connection(mongo_data, &uri, &connections, app_handle).await?;
+    let (_client, db) = get_mongodb_connection(mongo_data, &connections, app_handle).await?;
 
-    let collections = db.list_collection_names().await?;
+    let specs: Vec<CollectionSpecification> = db.list_collections().await?.try_collect().await?;
 
-    let mut all_stats = Vec::new();
+    // 并发获取各集合的统计信息，单个集合失败不影响整体连接
+    let all_stats = futures_util::stream::iter(specs)
+        .map(|spec| collection_summary(&db, spec))
+        .buffered(STATS_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
 
-    for collection_name in collections {
-        // 获取信息
-        let stats = db
-            .run_command(doc! {
-                "collStats": &collection_name,
-                "scale": 1,
-            })
-            .await?;
-
-        // 提取需要的字段
-        let mut coll_stats = Document::new();
-        coll_stats.insert("collection", collection_name);
+    Ok(all_stats)
+}
 
-        if let Ok(storage_size) = stats.get_i32("storageSize") {
-            coll_stats.insert("storageSize", storage_size);
+/// 获取单个集合的概要信息，标注类型为 `collection`、`view` 或 `timeseries`
+/// - 视图没有存储统计，仅返回其来源集合 `viewOn`
+/// - 获取统计失败（如权限不足）时在 `error` 字段中返回原因
+async fn collection_summary(db: &Database, spec: CollectionSpecification) -> Document {
+    let (collection_type, mut summary) = match spec.collection_type {
+        CollectionType::View => {
+            let mut summary = doc! { "collection": &spec.name };
+            if let Some(view_on) = spec.options.view_on {
+                summary.insert("viewOn", view_on);
+            }
+            ("view", summary)
         }
-
-        if let Ok(count) = stats.get_i32("count") {
-            coll_stats.insert("count", count);
+        ref collection_type => {
+            let summary = match collection_storage_stats(db, &spec.name).await {
+                Ok(storage_stats) => extract_coll_stats(&spec.name, &storage_stats),
+                Err(e) => {
+                    warn!("failed to get stats of {}: {}", spec.name, e);
+                    doc! { "collection": &spec.name, "error": e.to_string() }
+                }
+            };
+            let collection_type = if *collection_type == CollectionType::Timeseries {
+                "timeseries"
+            } else {
+                "collection"
+            };
+            (collection_type, summary)
         }
+    };
+    summary.insert("type", collection_type);
+    summary
+}
 
-        if let Ok(nindexes) = stats.get_i32("nindexes") {
-            coll_stats.insert("indexCount", nindexes);
-        }
+/// 通过 `$collStats` 聚合阶段获取集合的存储统计
+/// - `collStats` 命令已废弃，分片集合每个分片返回一条 `storageStats`
+pub async fn collection_storage_stats(
+    db: &Database,
+    collection_name: &str,
+) -> Result<Vec<Document>, Error> {
+    let stats: Vec<Document> = db
+        .collection::<Document>(collection_name)
+        .aggregate(vec![doc! { "$collStats": { "storageStats": { "scale": 1 } } }])
+        .await?
+        .try_collect()
+        .await?;
+    Ok(stats
+        .into_iter()
+        .filter_map(|stat| stat.get_document("storageStats").ok().cloned())
+        .collect())
+}
 
-        if let Ok(index_sizes) = stats.get_document("indexSizes") {
-            let index_names: Vec<_> = index_sizes.keys().collect();
-            coll_stats.insert("indexes", index_names);
+/// 从 `storageStats` 中提取集合统计信息
+/// - 数值字段可能是 Int32、Int64 或 Double，统一转换为 Int64
+/// - 多个分片的统计累加，平均文档大小按总量重新计算
+pub fn extract_coll_stats(collection_name: &str, storage_stats: &[Document]) -> Document {
+    let sum = |key: &str| -> i64 {
+        storage_stats
+            .iter()
+            .filter_map(|stats| stats.get(key).and_then(as_number))
+            .sum()
+    };
+    let size = sum("size");
+    let count = sum("count");
+
+    let mut index_sizes = Document::new();
+    for stats in storage_stats {
+        if let Ok(sizes) = stats.get_document("indexSizes") {
+            for (name, value) in sizes {
+                let total = index_sizes.get(name).and_then(as_number).unwrap_or(0)
+                    + as_number(value).unwrap_or(0);
+                index_sizes.insert(name.clone(), total);
+            }
         }
+    }
+    let index_names: Vec<String> = index_sizes.keys().cloned().collect();
+    let index_count = storage_stats
+        .iter()
+        .filter_map(|stats| stats.get("nindexes").and_then(as_number))
+        .max()
+        .unwrap_or(index_names.len() as i64);
 
-        all_stats.push(coll_stats);
+    doc! {
+        "collection": collection_name,
+        "size": size,
+        "count": count,
+        "avgObjSize": if count > 0 { size / count } else { 0 },
+        "storageSize": sum("storageSize"),
+        "totalIndexSize": sum("totalIndexSize"),
+        "indexCount": index_count,
+        "indexes": index_names,
+        "indexSizes": index_sizes,
+        "capped": storage_stats.iter().any(|stats| stats.get_bool("capped").unwrap_or(false)),
     }
+}
 
-    Ok(all_stats)
+/// 将任意数值类型的 BSON 转换为 `i64`，非数值返回 `None`
+pub fn as_number(value: &Bson) -> Option<i64> {
+    match value {
+        Bson::Int32(n) => Some(*n as i64),
+        Bson::Int64(n) => Some(*n),
+        Bson::Double(n) if n.is_finite() => Some(*n as i64),
+        _ => None,
+    }
 }
 
 /// 查询 MongoDB 集合的分页数据
 /// - 支持分页、查询条件解析和结果格式化
+/// - `keyset` 为真或传入 `continuation` 时使用键集分页，忽略 `page` 的偏移
 pub async fn mongodb_collection(
     mongo_data: &MongoData,
-    collection_name: String,
-    page: Option<u32>,
-    page_size: Option<u32>,
-    query: String,
+    params: CollectionParams,
     connections: State<'_, MongoConnections>,
     app_handle: AppHandle,
 ) -> Result<PaginatedResult, Error> {
-    let uri = format_uri(mongo_data, app_handle.clone())?;
-
-    let (_client, db) = get_mongodb_connection(mongo_data, &uri, &connections, app_handle).await?;
-    let collection: mongodb::Collection<Document> = db.collection(&collection_name);
-
-    // 解析查询条件，使用json5将不规范的json格式解析为严格规范的serde_json::Value再转为Document
-    let filter: Document = if !query.is_empty() {
-        let json: Value = json5::from_str(&preprocess_query(&query))?;
-        info!("Parsed: {:?}", json);
-        match to_document(&json) {
-            Ok(query) => query,
-            Err(_) => {
-                doc! {}
-            }
+    let (_client, db) = get_mongodb_connection(mongo_data, &connections, app_handle).await?;
+    let collection: mongodb::Collection<Document> = db.collection(&params.collection_name);
+
+    // 解析查询条件，直接将 JSON5 + shell 语法解析为 Document
+    // 语法错误直接返回，不能退化为空条件的全表查询
+    let convert_query = query_to_document(&params.query)?;
+    info!("Parsed: {:?}", convert_query);
+    let options = params.options.parse()?;
+
+    // 获取总文档数 - 使用过滤条件，排序规则和 hint 会影响匹配结果
+    let count_mode = resolve_count_mode(params.count_mode.unwrap_or_default(), convert_query.is_empty());
+    let count_limit = params.count_limit.unwrap_or(DEFAULT_COUNT_LIMIT);
+    let (total_count, total_exact) = match count_mode {
+        CountMode::Skip => (0, false),
+        CountMode::Estimated => {
+            let estimate_options = EstimatedDocumentCountOptions::builder()
+                .max_time(options.max_time)
+                .build();
+            let total = collection
+                .estimated_document_count()
+                .with_options(estimate_options)
+                .await?;
+            (total, false)
+        }
+        CountMode::Exact | CountMode::Capped => {
+            let count_options = CountOptions::builder()
+                .collation(options.collation.clone())
+                .hint(options.hint.clone())
+                .max_time(options.max_time)
+                .limit((count_mode == CountMode::Capped).then_some(count_probe_limit(count_limit) as u64))
+                .build();
+            let total = collection
+                .count_documents(convert_query.clone())
+                .with_options(count_options)
+                .await?;
+            cap_count(total, count_mode, count_limit)
         }
-    } else {
-        doc! {}
     };
 
-    // 字段类型转换
-    let convert_query = convert_document(filter);
-    info!("Converted: {:?}", convert_query);
-    // 获取总文档数 - 使用过滤条件
-    let total_count = collection.count_documents(convert_query.clone()).await?;
-
-    let page = page.unwrap_or(0);
-    let page_size = page_size.unwrap_or(25);
-    let skip = page * page_size;
+    let page = params.page.unwrap_or(0);
+    let page_size = params.page_size.unwrap_or(25);
     let total_pages = (total_count as f64 / page_size as f64).ceil() as u64;
+    let format = params.output_format.unwrap_or_default();
+
+    if params.keyset.unwrap_or(false) || params.continuation.is_some() {
+        // 键集分页：按排序键定位到上一页之后，多取一条判断是否还有下一页
+        let sort = keyset_sort(options.sort);
+        let filter = match params.continuation.as_deref() {
+            Some(token) if !token.is_empty() => {
+                merge_filter(convert_query, decode_token(token, &sort)?)
+            }
+            _ => convert_query,
+        };
+        let find_options = FindOptions::builder()
+            .limit(Some(page_size as i64 + 1))
+            .projection(keyset_projection(options.projection, &sort))
+            .sort(Some(sort.clone()))
+            .collation(options.collation)
+            .hint(options.hint)
+            .max_time(options.max_time)
+            .build();
+
+        let mut docs: Vec<Document> = collection
+            .find(filter)
+            .with_options(find_options)
+            .await?
+            .try_collect()
+            .await?;
+
+        let continuation = if docs.len() > page_size as usize {
+            docs.truncate(page_size as usize);
+            docs.last().map(|last| encode_token(&sort, last)).transpose()?
+        } else {
+            None
+        };
+
+        return Ok(PaginatedResult {
+            documents: docs.into_iter().map(|doc| format_document(doc, format)).collect(),
+            format,
+            total_count,
+            total_exact,
+            count_mode,
+            page,
+            page_size,
+            total_pages,
+            continuation,
+        });
+    }
 
     // 查询当前页数据 - 使用过滤条件
-    let options = FindOptions::builder()
+    let skip = page * page_size;
+    let find_options = FindOptions::builder()
         .skip(Some(skip as u64))
         .limit(Some(page_size as i64))
+        .projection(options.projection)
+        .sort(options.sort)
+        .collation(options.collation)
+        .hint(options.hint)
+        .max_time(options.max_time)
         .build();
 
-    let mut cursor = collection.find(convert_query).with_options(options).await?;
+    let mut cursor = collection.find(convert_query).with_options(find_options).await?;
 
     let mut documents = Vec::new();
     while let Some(doc) = cursor.try_next().await? {
-        let bson: Value = bson::to_bson(&doc)?.into();
-        documents.push(bson);
+        documents.push(format_document(doc, format));
     }
 
     Ok(PaginatedResult {
         documents,
+        format,
         total_count,
+        total_exact,
+        count_mode,
         page,
         page_size,
         total_pages,
+        continuation: None,
     })
 }
 
+/// 确定实际使用的计数方式，估算只适用于空条件
+pub fn resolve_count_mode(mode: CountMode, filter_is_empty: bool) -> CountMode {
+    match mode {
+        CountMode::Estimated if !filter_is_empty => CountMode::Capped,
+        mode => mode,
+    }
+}
+
+/// 限制计数时实际使用的 `$limit`，多数一条用于判断是否超出上限
+/// - 上限过大时不溢出，并限制在 `$limit` 支持的 i64 范围内
+pub fn count_probe_limit(limit: u64) -> i64 {
+    i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX)
+}
+
+/// 处理计数结果，超出上限时返回上限并标记为不精确
+pub fn cap_count(total: u64, mode: CountMode, limit: u64) -> (u64, bool) {
+    match mode {
+        CountMode::Capped if total > limit => (limit, false),
+        CountMode::Skip => (0, false),
+        CountMode::Estimated => (total, false),
+        _ => (total, true),
+    }
+}
+
+/// 打开服务端游标并返回第一批数据
+/// - 游标保存在连接池中，后续通过 `next_batch` 继续读取，不再重复执行查询
+pub async fn open_cursor(
+    mongo_data: &MongoData,
+    params: CursorParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<CursorBatch, Error> {
+    expire_cursors(&connections).await;
+    let (_client, db) = get_mongodb_connection(mongo_data, &connections, app_handle).await?;
+    let collection: mongodb::Collection<Document> = db.collection(&params.collection_name);
+
+    let filter = query_to_document(&params.query)?;
+    let options = params.options.parse()?;
+    let batch_size = params.batch_size.unwrap_or(25);
+
+    let find_options = FindOptions::builder()
+        .batch_size(Some(batch_size))
+        .projection(options.projection)
+        .sort(options.sort)
+        .collation(options.collation)
+        .hint(options.hint)
+        .max_time(options.max_time)
+        .build();
+    let cursor = collection.find(filter).with_options(find_options).await?;
+
+    let live_cursor = LiveCursor {
+        connection_id: mongo_data.id.clone(),
+        cursor,
+        format: params.output_format.unwrap_or_default(),
+        last_used: Instant::now(),
+    };
+    read_batch(ObjectId::new().to_hex(), live_cursor, batch_size, &connections).await
+}
+
+/// 从已打开的游标继续读取下一批数据
+pub async fn next_batch(
+    cursor_id: String,
+    batch_size: Option<u32>,
+    connections: State<'_, MongoConnections>,
+) -> Result<CursorBatch, Error> {
+    expire_cursors(&connections).await;
+    // 读取期间将游标移出连接池，避免长时间持有锁阻塞其他游标
+    let live_cursor = connections
+        .cursors
+        .lock()
+        .await
+        .remove(&cursor_id)
+        .ok_or_else(|| cursor_expired(&cursor_id))?;
+    read_batch(cursor_id, live_cursor, batch_size.unwrap_or(25), &connections).await
+}
+
+/// 关闭游标，游标被丢弃时驱动会通知服务端 killCursors
+pub async fn close_cursor(
+    cursor_id: String,
+    connections: State<'_, MongoConnections>,
+) -> Result<(), Error> {
+    connections.cursors.lock().await.remove(&cursor_id);
+    Ok(())
+}
+
+// 读取一批数据，游标未耗尽时放回连接池
+async fn read_batch(
+    cursor_id: String,
+    mut live_cursor: LiveCursor,
+    batch_size: u32,
+    connections: &MongoConnections,
+) -> Result<CursorBatch, Error> {
+    let mut documents = Vec::new();
+    let mut exhausted = false;
+    while documents.len() < batch_size as usize {
+        let next = live_cursor
+            .cursor
+            .try_next()
+            .await
+            .map_err(|e| match *e.kind {
+                // 服务端已关闭游标，与本地超时返回相同的提示
+                ErrorKind::Command(ref command_error)
+                    if command_error.code == CURSOR_NOT_FOUND_CODE =>
+                {
+                    cursor_expired(&cursor_id)
+                }
+                _ => e.into(),
+            })?;
+        match next {
+            Some(doc) => documents.push(format_document(doc, live_cursor.format)),
+            None => {
+                exhausted = true;
+                break;
+            }
+        }
+    }
+
+    let format = live_cursor.format;
+    let cursor_id = if exhausted {
+        None
+    } else {
+        live_cursor.last_used = Instant::now();
+        connections
+            .cursors
+            .lock()
+            .await
+            .insert(cursor_id.clone(), live_cursor);
+        Some(cursor_id)
+    };
+
+    Ok(CursorBatch {
+        cursor_id,
+        documents,
+        format,
+    })
+}
+
+// 游标不存在或已超时的错误
+fn cursor_expired(cursor_id: &str) -> Error {
+    anyhow::anyhow!("cursor {} does not exist or has expired", cursor_id).into()
+}
+
+/// 定期清理空闲超时的游标，应用启动时在后台运行
+/// - 没有新的游标调用时，空闲游标也会按时关闭并通知服务端
+pub async fn sweep_cursors(app_handle: AppHandle) {
+    let mut interval = tokio::time::interval(CURSOR_SWEEP_INTERVAL);
+    loop {
+        interval.tick().await;
+        expire_cursors(&app_handle.state::<MongoConnections>()).await;
+    }
+}
+
+// 清理空闲超时的游标
+async fn expire_cursors(connections: &MongoConnections) {
+    connections
+        .cursors
+        .lock()
+        .await
+        .retain(|_, live_cursor| live_cursor.last_used.elapsed() < CURSOR_IDLE_TIMEOUT);
+}
+
+/// 执行聚合管道并分页返回结果
+/// - 在管道末尾追加 `$skip` / `$limit` 实现分页，另以 `$count` 统计总数
+/// - 以 `$out` / `$merge` 结尾的管道不能追加阶段，直接执行并返回空结果
+pub async fn mongodb_aggregate(
+    mongo_data: &MongoData,
+    params: AggregateParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<PaginatedResult, Error> {
+    let (_client, db) = get_mongodb_connection(mongo_data, &connections, app_handle).await?;
+    let collection: mongodb::Collection<Document> = db.collection(&params.collection_name);
+
+    let pipeline = parse_pipeline(&params.pipeline)?;
+    info!("Pipeline: {:?}", pipeline);
+    mongo_data.ensure_pipeline_allowed(&pipeline)?;
+
+    let options = AggregateOptions::builder()
+        .allow_disk_use(params.allow_disk_use)
+        .build();
+
+    let page = params.page.unwrap_or(0);
+    let page_size = params.page_size.unwrap_or(25);
+    let format = params.output_format.unwrap_or_default();
+
+    let writes_output = pipeline.last().and_then(output_stage).is_some();
+    if writes_output {
+        collection
+            .aggregate(pipeline)
+            .with_options(options)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        // 结果写入了其他集合，没有统计总数
+        return Ok(PaginatedResult {
+            documents: Vec::new(),
+            format,
+            total_count: 0,
+            total_exact: false,
+            count_mode: CountMode::Skip,
+            page,
+            page_size,
+            total_pages: 0,
+            continuation: None,
+        });
+    }
+
+    // 获取管道输出的总文档数，管道没有元数据可估算，估算按限制计数处理
+    let count_mode = resolve_count_mode(params.count_mode.unwrap_or_default(), false);
+    let count_limit = params.count_limit.unwrap_or(DEFAULT_COUNT_LIMIT);
+    let (total_count, total_exact) = if count_mode == CountMode::Skip {
+        (0, false)
+    } else {
+        let mut count_pipeline = pipeline.clone();
+        if count_mode == CountMode::Capped {
+            count_pipeline.push(doc! { "$limit": count_probe_limit(count_limit) });
+        }
+        count_pipeline.push(doc! { "$count": "total" });
+        let total = match collection
+            .aggregate(count_pipeline)
+            .with_options(options.clone())
+            .await?
+            .try_next()
+            .await?
+        {
+            Some(count) => match count.get("total") {
+                Some(Bson::Int32(n)) => *n as u64,
+                Some(Bson::Int64(n)) => *n as u64,
+                _ => 0,
+            },
+            None => 0,
+        };
+        cap_count(total, count_mode, count_limit)
+    };
+    let total_pages = (total_count as f64 / page_size as f64).ceil() as u64;
+
+    // 查询当前页数据
+    let mut page_pipeline = pipeline;
+    page_pipeline.push(doc! { "$skip": (page as i64) * (page_size as i64) });
+    page_pipeline.push(doc! { "$limit": page_size as i64 });
+
+    let mut cursor = collection.aggregate(page_pipeline).with_options(options).await?;
+
+    let mut documents = Vec::new();
+    while let Some(doc) = cursor.try_next().await? {
+        documents.push(format_document(doc, format));
+    }
+
+    Ok(PaginatedResult {
+        documents,
+        format,
+        total_count,
+        total_exact,
+        count_mode,
+        page,
+        page_size,
+        total_pages,
+        continuation: None,
+    })
+}
+
+/// 按指定格式输出查询结果中的文档
+pub fn format_document(doc: Document, format: OutputFormat) -> Value {
+    match format {
+        OutputFormat::Canonical => Bson::Document(doc).into_canonical_extjson(),
+        OutputFormat::Relaxed => Bson::Document(doc).into_relaxed_extjson(),
+        OutputFormat::Shell => Value::String(document_to_shell_string(&doc)),
+    }
+}
+
 /// 清理指定 ID 的 MongoDB 连接
 /// - 从连接池中移除连接，并关闭 SSH 隧道（如果存在）
 pub async fn clear_connection(
     id: String,
     connections: State<'_, MongoConnections>, //manage自动注入，'_匿名生命周期自动推断
 ) -> Result<(), Error> {
+    // 关闭该连接上所有未读完的游标
+    connections
+        .cursors
+        .lock()
+        .await
+        .retain(|_, live_cursor| live_cursor.connection_id != id);
+
     let mut connections_write = connections.connections.write().await;
 
     //利用remove() 从 HashMap 中移除整个条目，可以拥有整个 collecion 的所有权，包括 ssh_tunnel_manage
     if let Some(collecion) = connections_write.remove(&id) {
-        if let Some(ssh_tunnel_manage) = collecion.2 {
+        if let Some(ssh_tunnel_manage) = collecion.ssh_tunnel_manager {
             ssh_tunnel_manage.stop().await;
         }
     }
@@ -0,0 +1,184 @@
+use super::mongo::{get_database, run_command, MongoConnections, MongoData, QueryOptions};
+use crate::{error::Error, utils::q2b::*};
+use log::info;
+use mongodb::{
+    bson::{self, doc, Bson, Document},
+    options::Hint,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+// explain 的详细程度，与 mongosh 的 explain() 参数一致
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ExplainVerbosity {
+    // 只选择查询计划，不实际执行
+    #[default]
+    QueryPlanner,
+    // 执行胜出的计划并统计扫描数量和耗时
+    ExecutionStats,
+    // 同时执行所有候选计划
+    AllPlansExecution,
+}
+
+impl ExplainVerbosity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExplainVerbosity::QueryPlanner => "queryPlanner",
+            ExplainVerbosity::ExecutionStats => "executionStats",
+            ExplainVerbosity::AllPlansExecution => "allPlansExecution",
+        }
+    }
+}
+
+// 从 explain 结果中提取的摘要，执行统计仅在 executionStats 及以上可用
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct ExplainSummary {
+    // 胜出计划的阶段，自顶向下，如 ["FETCH", "IXSCAN"]
+    pub stages: Vec<String>,
+    // 使用的索引名称，为空表示没有走索引
+    pub indexes: Vec<String>,
+    pub collection_scan: bool,
+    pub docs_examined: Option<u64>,
+    pub keys_examined: Option<u64>,
+    pub docs_returned: Option<u64>,
+    pub execution_time_ms: Option<u64>,
+}
+
+// explain 查询参数，与集合查询相同
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainParams {
+    pub collection_name: String,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub query: String,
+    #[serde(flatten)]
+    pub options: QueryOptions,
+    pub verbosity: Option<ExplainVerbosity>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExplainResult {
+    summary: ExplainSummary,
+    // 服务器返回的原始 explain 文档
+    raw: Value,
+}
+
+/// 以 explain 方式执行与 `mongodb_collection` 相同的查询
+/// - 过滤条件、投影、排序、collation、hint 和分页与集合查询一致
+/// - 返回结构化摘要以及原始 explain 文档
+pub async fn mongodb_explain(
+    mongo_data: &MongoData,
+    params: ExplainParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<ExplainResult, Error> {
+    let filter = query_to_document(&params.query)?;
+    let options = params.options.parse()?;
+    let page = params.page.unwrap_or(0);
+    let page_size = params.page_size.unwrap_or(25);
+    let collection_name = params.collection_name;
+
+    let mut find = doc! {
+        "find": &collection_name,
+        "filter": filter,
+        "skip": (page as i64) * (page_size as i64),
+        "limit": page_size as i64,
+    };
+    if let Some(projection) = options.projection {
+        find.insert("projection", projection);
+    }
+    if let Some(sort) = options.sort {
+        find.insert("sort", sort);
+    }
+    if let Some(collation) = options.collation {
+        find.insert("collation", bson::to_bson(&collation)?);
+    }
+    match options.hint {
+        Some(Hint::Keys(keys)) => {
+            find.insert("hint", keys);
+        }
+        Some(Hint::Name(name)) => {
+            find.insert("hint", name);
+        }
+        _ => {}
+    }
+    if let Some(max_time) = options.max_time {
+        find.insert("maxTimeMS", max_time.as_millis() as i64);
+    }
+
+    let verbosity = params.verbosity.unwrap_or_default();
+    let db = get_database(mongo_data, &connections, app_handle).await?;
+    let explain = run_command(
+        mongo_data,
+        &db,
+        doc! { "explain": find, "verbosity": verbosity.as_str() },
+    )
+    .await?;
+    info!(
+        "explained query on {} at {}",
+        collection_name,
+        verbosity.as_str()
+    );
+
+    Ok(ExplainResult {
+        summary: summarize_explain(&explain),
+        raw: Bson::Document(explain).into_relaxed_extjson(),
+    })
+}
+
+/// 从 explain 文档中提取胜出计划和执行统计
+/// - 兼容经典执行引擎、SBE（`winningPlan.queryPlan`）以及分片集群（`shards`）的结构
+pub fn summarize_explain(explain: &Document) -> ExplainSummary {
+    let mut summary = ExplainSummary::default();
+    if let Ok(winning_plan) = explain
+        .get_document("queryPlanner")
+        .and_then(|planner| planner.get_document("winningPlan"))
+    {
+        collect_stages(winning_plan, &mut summary);
+    }
+    summary.collection_scan = summary.stages.iter().any(|stage| stage == "COLLSCAN");
+
+    if let Ok(stats) = explain.get_document("executionStats") {
+        summary.docs_examined = get_u64(stats, "totalDocsExamined");
+        summary.keys_examined = get_u64(stats, "totalKeysExamined");
+        summary.docs_returned = get_u64(stats, "nReturned");
+        summary.execution_time_ms = get_u64(stats, "executionTimeMillis");
+    }
+    summary
+}
+
+// 深度优先遍历计划树，记录阶段名称和使用的索引
+fn collect_stages(plan: &Document, summary: &mut ExplainSummary) {
+    if let Ok(stage) = plan.get_str("stage") {
+        summary.stages.push(stage.to_string());
+    }
+    if let Ok(index_name) = plan.get_str("indexName") {
+        if !summary.indexes.iter().any(|name| name == index_name) {
+            summary.indexes.push(index_name.to_string());
+        }
+    }
+    for key in ["queryPlan", "inputStage", "winningPlan"] {
+        if let Ok(child) = plan.get_document(key) {
+            collect_stages(child, summary);
+        }
+    }
+    for key in ["inputStages", "shards"] {
+        if let Ok(children) = plan.get_array(key) {
+            for child in children.iter().filter_map(Bson::as_document) {
+                collect_stages(child, summary);
+            }
+        }
+    }
+}
+
+fn get_u64(doc: &Document, key: &str) -> Option<u64> {
+    match doc.get(key)? {
+        Bson::Int32(n) => u64::try_from(*n).ok(),
+        Bson::Int64(n) => u64::try_from(*n).ok(),
+        Bson::Double(n) if *n >= 0.0 => Some(*n as u64),
+        _ => None,
+    }
+}
@@ -0,0 +1,164 @@
+use super::mongo::{
+    as_number, collection_storage_stats, extract_coll_stats, get_database, run_command,
+    MongoConnections, MongoData,
+};
+use crate::{error::Error, utils::q2b::*};
+use futures_util::stream::TryStreamExt;
+use log::{info, warn};
+use mongodb::{
+    bson::{self, doc, Bson, Document},
+    options::IndexOptions,
+    IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+// 索引信息返回，文档类字段使用 relaxed 扩展 JSON
+#[derive(Serialize, Deserialize)]
+pub struct IndexInfo {
+    name: String,
+    keys: Value,
+    unique: bool,
+    sparse: bool,
+    hidden: bool,
+    partial_filter: Option<Value>,
+    // TTL 索引的过期秒数
+    expire_after_seconds: Option<u64>,
+    collation: Option<Value>,
+    // 索引占用的字节数，取自 $collStats 的 indexSizes
+    size: Option<i64>,
+}
+
+/// 列出集合的全部索引
+/// - 索引定义来自 `listIndexes`，大小来自 `$collStats`
+/// - 没有 `$collStats` 权限或服务器不支持时只记录日志，索引大小留空
+pub async fn list_indexes(
+    mongo_data: &MongoData,
+    collection_name: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<Vec<IndexInfo>, Error> {
+    let db = get_database(mongo_data, &connections, app_handle).await?;
+    let collection = db.collection::<Document>(&collection_name);
+
+    let models: Vec<IndexModel> = collection.list_indexes().await?.try_collect().await?;
+    let storage_stats = collection_storage_stats(&db, &collection_name)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("failed to read index sizes of {}: {}", collection_name, e);
+            Vec::new()
+        });
+    let coll_stats = extract_coll_stats(&collection_name, &storage_stats);
+    let index_sizes = coll_stats.get_document("indexSizes").ok();
+
+    let mut indexes = Vec::new();
+    for model in models {
+        let options = model.options.unwrap_or_default();
+        let name = options.name.unwrap_or_default();
+        let size = index_sizes.and_then(|sizes| sizes.get(&name).and_then(as_number));
+        indexes.push(IndexInfo {
+            keys: Bson::Document(model.keys).into_relaxed_extjson(),
+            unique: options.unique.unwrap_or(false),
+            sparse: options.sparse.unwrap_or(false),
+            hidden: options.hidden.unwrap_or(false),
+            partial_filter: options
+                .partial_filter_expression
+                .map(|filter| Bson::Document(filter).into_relaxed_extjson()),
+            expire_after_seconds: options.expire_after.map(|ttl| ttl.as_secs()),
+            collation: options
+                .collation
+                .map(|collation| bson::to_bson(&collation).map(Bson::into_relaxed_extjson))
+                .transpose()?,
+            name,
+            size,
+        });
+    }
+    Ok(indexes)
+}
+
+/// 创建索引
+/// - `keys` 为索引键文档，如 `{ a: 1, b: -1 }`
+/// - `index_options` 与 mongosh 的 `createIndex` 选项相同，如 `{ unique: true, expireAfterSeconds: 3600 }`
+/// - 返回创建的索引名称
+pub async fn create_index(
+    mongo_data: &MongoData,
+    collection_name: String,
+    keys: String,
+    index_options: Option<String>,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<String, Error> {
+    mongo_data.ensure_writable("createIndexes")?;
+    let keys = query_to_document(&keys)?;
+    if keys.is_empty() {
+        return Err(anyhow::anyhow!("index keys must not be empty").into());
+    }
+    let options = parse_index_options(index_options.as_deref())?;
+    let model = IndexModel::builder().keys(keys).options(options).build();
+
+    let db = get_database(mongo_data, &connections, app_handle).await?;
+    let result = db
+        .collection::<Document>(&collection_name)
+        .create_index(model)
+        .await?;
+    info!("created index {} on {}", result.index_name, collection_name);
+    Ok(result.index_name)
+}
+
+/// 解析 mongosh 风格的索引选项，未传或空字符串视为无选项
+pub fn parse_index_options(text: Option<&str>) -> Result<Option<IndexOptions>, Error> {
+    Ok(optional_document(text)?
+        .map(bson::from_document)
+        .transpose()?)
+}
+
+/// 按名称删除索引，`_id_` 索引不能删除
+pub async fn drop_index(
+    mongo_data: &MongoData,
+    collection_name: String,
+    index_name: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<(), Error> {
+    mongo_data.ensure_writable("dropIndexes")?;
+    if index_name == "_id_" {
+        return Err(anyhow::anyhow!("the _id index cannot be dropped").into());
+    }
+    let db = get_database(mongo_data, &connections, app_handle).await?;
+    db.collection::<Document>(&collection_name)
+        .drop_index(&index_name)
+        .await?;
+    info!("dropped index {} on {}", index_name, collection_name);
+    Ok(())
+}
+
+/// 隐藏或取消隐藏索引，隐藏的索引仍会维护但不参与查询计划
+/// - 需要 MongoDB 4.4 及以上版本
+pub async fn hide_index(
+    mongo_data: &MongoData,
+    collection_name: String,
+    index_name: String,
+    hidden: bool,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<(), Error> {
+    if index_name == "_id_" {
+        return Err(anyhow::anyhow!("the _id index cannot be hidden").into());
+    }
+    let db = get_database(mongo_data, &connections, app_handle).await?;
+    run_command(
+        mongo_data,
+        &db,
+        doc! {
+            "collMod": &collection_name,
+            "index": { "name": &index_name, "hidden": hidden },
+        },
+    )
+    .await?;
+    info!(
+        "set index {} on {} hidden: {}",
+        index_name, collection_name, hidden
+    );
+    Ok(())
+}
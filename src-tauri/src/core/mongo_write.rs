@@ -0,0 +1,301 @@
+use super::mongo::{get_collection, MongoConnections, MongoData};
+use crate::{error::Error, utils::q2b::*};
+use log::info;
+use mongodb::{
+    bson::{Bson, Document},
+    options::{ReplaceOptions, UpdateModifications, UpdateOptions},
+    results::UpdateResult,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, State};
+
+// 写操作结果返回，未涉及的计数为 0
+#[derive(Serialize, Deserialize, Default)]
+pub struct WriteResult {
+    inserted_ids: Vec<Value>,
+    matched_count: u64,
+    modified_count: u64,
+    deleted_count: u64,
+    upserted_id: Option<Value>,
+}
+
+impl From<UpdateResult> for WriteResult {
+    fn from(result: UpdateResult) -> Self {
+        WriteResult {
+            matched_count: result.matched_count,
+            modified_count: result.modified_count,
+            upserted_id: result.upserted_id.map(Bson::into_relaxed_extjson),
+            ..Default::default()
+        }
+    }
+}
+
+// 更新参数
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateParams {
+    pub collection_name: String,
+    pub filter: String,
+    // 更新操作符文档或更新管道
+    pub update: String,
+    pub upsert: Option<bool>,
+}
+
+// 替换参数
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceParams {
+    pub collection_name: String,
+    pub filter: String,
+    pub replacement: String,
+    pub upsert: Option<bool>,
+}
+
+// 写操作的种类，用于检查参数
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WriteKind {
+    One,
+    Many,
+}
+
+/// 解析写操作的过滤条件
+/// - 批量更新和删除不允许空条件，避免误操作整个集合，需要时显式写 `{ _id: { $exists: true } }`
+fn parse_write_filter(filter: &str, kind: WriteKind) -> Result<Document, Error> {
+    let filter = query_to_document(filter)?;
+    if kind == WriteKind::Many && filter.is_empty() {
+        return Err(
+            anyhow::anyhow!("an empty filter is not allowed for multi-document writes").into(),
+        );
+    }
+    Ok(filter)
+}
+
+/// 插入单个文档
+pub async fn insert_one(
+    mongo_data: &MongoData,
+    collection_name: String,
+    document: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    mongo_data.ensure_writable("insertOne")?;
+    let document = query_to_document(&document)?;
+    let collection = get_collection(mongo_data, &collection_name, &connections, app_handle).await?;
+
+    let result = collection.insert_one(document).await?;
+    info!(
+        "inserted into {}: {:?}",
+        collection_name, result.inserted_id
+    );
+    Ok(WriteResult {
+        inserted_ids: vec![result.inserted_id.into_relaxed_extjson()],
+        ..Default::default()
+    })
+}
+
+/// 批量插入文档
+pub async fn insert_many(
+    mongo_data: &MongoData,
+    collection_name: String,
+    documents: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    mongo_data.ensure_writable("insertMany")?;
+    let documents = parse_documents(&documents)?;
+    if documents.is_empty() {
+        return Err(anyhow::anyhow!("no documents to insert").into());
+    }
+    let collection = get_collection(mongo_data, &collection_name, &connections, app_handle).await?;
+
+    let result = collection.insert_many(documents).await?;
+    // inserted_ids 按插入顺序的下标排列
+    let mut inserted: Vec<_> = result.inserted_ids.into_iter().collect();
+    inserted.sort_by_key(|(index, _)| *index);
+    info!(
+        "inserted {} documents into {}",
+        inserted.len(),
+        collection_name
+    );
+    Ok(WriteResult {
+        inserted_ids: inserted
+            .into_iter()
+            .map(|(_, id)| id.into_relaxed_extjson())
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// 更新单个文档
+pub async fn update_one(
+    mongo_data: &MongoData,
+    params: UpdateParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    update_documents(mongo_data, params, WriteKind::One, connections, app_handle).await
+}
+
+/// 更新所有匹配的文档
+pub async fn update_many(
+    mongo_data: &MongoData,
+    params: UpdateParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    update_documents(mongo_data, params, WriteKind::Many, connections, app_handle).await
+}
+
+async fn update_documents(
+    mongo_data: &MongoData,
+    params: UpdateParams,
+    kind: WriteKind,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    mongo_data.ensure_writable(match kind {
+        WriteKind::One => "updateOne",
+        WriteKind::Many => "updateMany",
+    })?;
+    let UpdateParams {
+        collection_name,
+        filter,
+        update,
+        upsert,
+    } = params;
+    let filter = parse_write_filter(&filter, kind)?;
+    let update = parse_update(&update)?;
+    if let UpdateModifications::Document(doc) = &update {
+        // 更新文档只能包含 $ 操作符，整体替换应使用 replace_one
+        if doc.is_empty() || doc.keys().any(|k| !k.starts_with('$')) {
+            return Err(
+                anyhow::anyhow!("update document must only contain update operators").into(),
+            );
+        }
+    }
+    let collection = get_collection(mongo_data, &collection_name, &connections, app_handle).await?;
+
+    let options = UpdateOptions::builder().upsert(upsert).build();
+    let result = match kind {
+        WriteKind::One => {
+            collection
+                .update_one(filter, update)
+                .with_options(options)
+                .await?
+        }
+        WriteKind::Many => {
+            collection
+                .update_many(filter, update)
+                .with_options(options)
+                .await?
+        }
+    };
+    info!(
+        "updated {}: matched {}, modified {}",
+        collection_name, result.matched_count, result.modified_count
+    );
+    Ok(result.into())
+}
+
+/// 替换单个文档
+pub async fn replace_one(
+    mongo_data: &MongoData,
+    params: ReplaceParams,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    mongo_data.ensure_writable("replaceOne")?;
+    let ReplaceParams {
+        collection_name,
+        filter,
+        replacement,
+        upsert,
+    } = params;
+    let filter = parse_write_filter(&filter, WriteKind::One)?;
+    let replacement = query_to_document(&replacement)?;
+    if replacement.keys().any(|k| k.starts_with('$')) {
+        return Err(
+            anyhow::anyhow!("replacement document must not contain update operators").into(),
+        );
+    }
+    let collection = get_collection(mongo_data, &collection_name, &connections, app_handle).await?;
+
+    let options = ReplaceOptions::builder().upsert(upsert).build();
+    let result = collection
+        .replace_one(filter, replacement)
+        .with_options(options)
+        .await?;
+    info!(
+        "replaced {}: matched {}, modified {}",
+        collection_name, result.matched_count, result.modified_count
+    );
+    Ok(result.into())
+}
+
+/// 删除单个文档
+pub async fn delete_one(
+    mongo_data: &MongoData,
+    collection_name: String,
+    filter: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    delete_documents(
+        mongo_data,
+        collection_name,
+        filter,
+        WriteKind::One,
+        connections,
+        app_handle,
+    )
+    .await
+}
+
+/// 删除所有匹配的文档
+pub async fn delete_many(
+    mongo_data: &MongoData,
+    collection_name: String,
+    filter: String,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    delete_documents(
+        mongo_data,
+        collection_name,
+        filter,
+        WriteKind::Many,
+        connections,
+        app_handle,
+    )
+    .await
+}
+
+async fn delete_documents(
+    mongo_data: &MongoData,
+    collection_name: String,
+    filter: String,
+    kind: WriteKind,
+    connections: State<'_, MongoConnections>,
+    app_handle: AppHandle,
+) -> Result<WriteResult, Error> {
+    mongo_data.ensure_writable(match kind {
+        WriteKind::One => "deleteOne",
+        WriteKind::Many => "deleteMany",
+    })?;
+    let filter = parse_write_filter(&filter, kind)?;
+    let collection = get_collection(mongo_data, &collection_name, &connections, app_handle).await?;
+
+    let result = match kind {
+        WriteKind::One => collection.delete_one(filter).await?,
+        WriteKind::Many => collection.delete_many(filter).await?,
+    };
+    info!(
+        "deleted {} documents from {}",
+        result.deleted_count, collection_name
+    );
+    Ok(WriteResult {
+        deleted_count: result.deleted_count,
+        ..Default::default()
+    })
+}
@@ -1,170 +1,140 @@
-use crate::{
-    utils::crypt::{decrypt, encrypt},
-    SSH_KEY_FILE,
-};
-use anyhow::Context;
-use russh::client::{self, Handler};
+use super::ssh_client::{next_request_id, PromptClock};
+use crate::{utils::crypt::encrypt, SSH_KEY_FILE};
+use anyhow::{anyhow, Context};
+use log::{error, info, warn};
+use russh::client::Handler;
 use russh::keys::{HashAlg, PublicKey};
-use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Listener};
-use log::{error, info, warn};
+use tokio::sync::oneshot;
+use tokio::time::{timeout, Duration};
 
-#[derive(Default)]
-struct KeyCheckResult {
-    fingerprint: String,
-    algorithm: String,
-    openssh: String,
-}
+// 服务器公钥校验
+// 在 SSH 握手过程中校验服务器公钥：已保存公钥时比对，首次连接时询问前台是否信任，
+// 确认后保存公钥并继续同一次握手，不再需要重新连接。
 
-// 共享的数据结构，用于存储服务器密钥的检查结果
-struct ServerKeyCheck {
-    key_data: Arc<Mutex<KeyCheckResult>>,
-}
+// 等待前台确认信任服务器公钥的时间
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(120);
 
 // 事件发送数据结构，用于首次密钥检查时发送给前台
 #[derive(serde::Serialize, Clone)]
 struct FirstKeyCheckData {
+    // 请求编号，前台以 `FirstKeyChecked-{id}` 事件回答
+    id: u64,
+    // 多跳时区分是哪一台服务器
+    host: String,
     alg: String,
     fp: String,
 }
 
-impl Handler for ServerKeyCheck {
-    type Error = russh::Error;
+/// SSH 连接的 Handler，握手时校验服务器公钥
+pub struct ServerKeyCheck {
+    // 公钥的保存键，多跳时每一跳各自保存
+    id: String,
+    // host:port，用于日志和前台提示
+    host: String,
+    // 已保存的 OpenSSH 格式公钥，首次连接时为空
+    saved_key: Option<String>,
+    // 等待前台确认的时间不计入连接超时
+    clock: PromptClock,
+    app_handle: AppHandle,
+}
 
-    async fn check_server_key(
-        &mut self,
-        server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        let alg = server_public_key.algorithm();
-        let fp = server_public_key.fingerprint(HashAlg::Sha256);
+impl ServerKeyCheck {
+    pub fn new(
+        id: &str,
+        host: &str,
+        port: u16,
+        saved_key: Option<String>,
+        clock: PromptClock,
+        app_handle: AppHandle,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            host: format!("{}:{}", host, port),
+            saved_key,
+            clock,
+            app_handle,
+        }
+    }
 
-        // 更新共享状态
-        if let Ok(mut result) = self.key_data.lock() {
-            result.fingerprint = fp.to_string();
-            result.algorithm = alg.to_string();
-            result.openssh = server_public_key.to_openssh()?;
+    // 首次连接，发送事件等待前台确认是否信任，确认后保存公钥
+    async fn confirm_first_key(&self, server_public_key: &PublicKey, openssh: String) -> anyhow::Result<bool> {
+        let alg = server_public_key.algorithm().to_string();
+        let fp = server_public_key.fingerprint(HashAlg::Sha256).to_string();
+        warn!(
+            "first check the key of host: {} | algorithm: {} | fingerprint: {}",
+            self.host, alg, fp
+        );
+
+        let id = next_request_id();
+        let (confirm_tx, confirm_rx) = oneshot::channel();
+        // 先注册一次性监听，再发送事件，避免错过前台的确认
+        let event_id = self.app_handle.once(format!("FirstKeyChecked-{}", id), move |event| {
+            let _ = confirm_tx.send(event.payload() == "true");
+        });
+        self.app_handle
+            .emit(
+                "FirstKeyCheck",
+                FirstKeyCheckData {
+                    id,
+                    host: self.host.clone(),
+                    alg,
+                    fp,
+                },
+            )
+            .context("Failed to emit event")?;
+
+        let trusted = match self.clock.pause(timeout(CONFIRM_TIMEOUT, confirm_rx)).await {
+            Ok(Ok(trusted)) => trusted,
+            _ => {
+                self.app_handle.unlisten(event_id);
+                return Err(anyhow!("server key confirmation timed out"));
+            }
+        };
+        if trusted {
+            encrypt(self.id.clone(), openssh, self.app_handle.clone(), SSH_KEY_FILE)
+                .context("failed to encrypt")?;
+            info!("server key of {} has been trusted", self.host);
         }
-        Ok(true)
+        Ok(trusted)
     }
 }
 
-/// 执行服务器密钥检查的主函数
-/// - `app_handle`: Tauri 应用句柄
-/// 返回：密钥的 OpenSSH 格式或错误信息
-pub async fn key_check(
-    id: &str,
-    host: &str,
-    port: u16,
-    user: &str,
-    pass: &str,
-    app_handle: AppHandle,
-) -> anyhow::Result<String> {
-    let cfg = Arc::new(client::Config::default());
-
-    let check_result = Arc::new(Mutex::new(KeyCheckResult::default()));
-
-    let handler = ServerKeyCheck {
-        key_data: check_result.clone(),
-    };
-
-    let addr = format!("{host}:{port}");
-    let mut handle = client::connect(cfg, &addr, handler).await?;
-
-    match handle.authenticate_password(user, pass).await? {
-        client::AuthResult::Success => {
-            if let Ok(result) = check_result.lock() {
-                let decrypt_key = decrypt(id, app_handle.clone(), SSH_KEY_FILE)?;
-
-                if decrypt_key.is_empty() {
-                    warn!(
-                        "first check the key of host: {}:{} | algorithm: {} | fingerprint: {}",
-                        host, port, result.algorithm, result.fingerprint
-                    );
-
-                    let id_clone = id.to_string();
-                    let app_handle_clone = app_handle.clone();
-                    let openssh = result.openssh.clone();
-                    let algorithm = result.algorithm.clone();
-                    let fingerprint = result.fingerprint.clone();
+impl Handler for ServerKeyCheck {
+    type Error = anyhow::Error;
 
-                    // emit需要所有权 clone
-					// 发送事件，等待前台确认保存密钥
-                    app_handle
-                        .emit(
-                            "FirstKeyCheck",
-                            FirstKeyCheckData {
-                                alg: algorithm,
-                                fp: fingerprint,
-                            },
-                        )
-                        .context("Failed to emit event")?;
-					
-					// 一次监听前台确认事件
-                    app_handle.once("FirstKeyChecked", |event| {
-                        if event.payload() == "true" {
-                            let _ = encrypt(id_clone, openssh, app_handle_clone, SSH_KEY_FILE)
-                                .context("failed to encrypt");
-                        }
-                    });
-                } else {
-                    if decrypt_key == result.openssh {
-                        info!("authentication checked successfully");
-                        if let Some(key) = decrypt_key.split_whitespace().nth(1) {
-                            return Ok(key.to_string());
-                        } else {
-                            error!("error sshkey format");
-                            return Err(anyhow::anyhow!("error sshkey format"));
-                        }
-                    } else {
-                        error!("Key verification failed");
-                        return Err(anyhow::anyhow!("Code-3067"));
-                    }
-                }
-            }
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let openssh = server_public_key.to_openssh()?;
+        if let Some(saved_key) = &self.saved_key {
+            check_saved_key(saved_key, &openssh)?;
+            info!("authentication checked successfully");
+            return Ok(true);
         }
-        client::AuthResult::Failure { .. } => {
-            error!("Password verification failed");
-            return Err(anyhow::anyhow!("Password verification failed"));
+        if !self.confirm_first_key(server_public_key, openssh).await? {
+            // 前台不信任服务器公钥，以特定代号告诉前台不是错误，用户主动取消了连接
+            return Err(anyhow!("Code-3581"));
         }
+        Ok(true)
     }
-    Ok("".to_string())
 }
 
-
-
-#[tokio::test]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let host = "127.0.0.1";
-    let port = 22;
-    let pass = "asdfghjkl";
-    let user = "root";
-
-    let cfg = Arc::new(client::Config::default());
-
-    let check_result = Arc::new(Mutex::new(KeyCheckResult::default()));
-
-    let handler = ServerKeyCheck {
-        key_data: check_result.clone(), 
-    };
-
-    let addr = format!("{host}:{port}");
-    let mut handle = client::connect(cfg, &addr, handler).await?;
-
-    match handle.authenticate_password(user, pass).await? {
-        client::AuthResult::Success => {
-            println!("authentication checked successfully");
-            if let Ok(result) = check_result.lock() {
-                println!("fingerprint: {}", result.fingerprint);
-                println!("algorithm: {}", result.algorithm);
-                println!("openssh: {}", result.openssh);
-                if let Some(key) = result.openssh.split_whitespace().nth(1) {
-                    println!("key: {key}");
-                }
-            }
-        }
-        client::AuthResult::Failure { .. } => {
-            eprintln!("Password verification failed");
+/// 比对服务器公钥与首次连接时保存的公钥
+/// - `saved_key` 与 `openssh` 均为 OpenSSH 格式 "算法 base64"
+/// - 不一致时返回 `Code-3067`，可能是中间人攻击或服务器更换了密钥
+pub fn check_saved_key(saved_key: &str, openssh: &str) -> anyhow::Result<()> {
+    let saved = match saved_key.split_whitespace().nth(1) {
+        Some(saved) => saved,
+        None => {
+            error!("error sshkey format");
+            return Err(anyhow!("error sshkey format"));
         }
+    };
+    if openssh.split_whitespace().nth(1) != Some(saved) {
+        error!("Key verification failed");
+        return Err(anyhow!("Code-3067"));
     }
     Ok(())
 }
@@ -0,0 +1,113 @@
+use anyhow::{anyhow, bail, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// SOCKS5 代理服务端（RFC 1928 / RFC 1929）
+// SSH 隧道在本地提供 SOCKS5 入口，MongoDB 驱动通过它以节点的真实主机名建立连接，
+// 拓扑发现、故障转移和 TLS 主机名校验都与直连时一致。
+// 入口只监听 127.0.0.1，并使用每条隧道随机生成的用户名和密码，避免本机其他程序借用隧道。
+
+const VERSION: u8 = 0x05;
+const AUTH_VERSION: u8 = 0x01;
+const METHOD_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xFF;
+const COMMAND_CONNECT: u8 = 0x01;
+const ADDRESS_IPV4: u8 = 0x01;
+const ADDRESS_DOMAIN: u8 = 0x03;
+const ADDRESS_IPV6: u8 = 0x04;
+
+/// 回复客户端的结果代码
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reply {
+    Succeeded = 0x00,
+    GeneralFailure = 0x01,
+    HostUnreachable = 0x04,
+    CommandNotSupported = 0x07,
+    AddressTypeNotSupported = 0x08,
+}
+
+/// 完成协商和认证，读取 CONNECT 请求的目标地址
+/// - 只支持用户名密码认证和 CONNECT 命令，其他请求回复错误后返回 Err
+/// - 目标主机名原样返回，由 SSH 服务器解析
+pub async fn accept_request<S>(stream: &mut S, username: &str, password: &str) -> Result<(String, u16)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // 协商认证方式
+    let mut header = [0u8; 2];
+    stream.read_exact(&mut header).await?;
+    if header[0] != VERSION {
+        bail!("unsupported SOCKS version {}", header[0]);
+    }
+    let mut methods = vec![0u8; header[1] as usize];
+    stream.read_exact(&mut methods).await?;
+    if !methods.contains(&METHOD_PASSWORD) {
+        stream.write_all(&[VERSION, METHOD_NONE_ACCEPTABLE]).await?;
+        bail!("SOCKS client does not offer username/password authentication");
+    }
+    stream.write_all(&[VERSION, METHOD_PASSWORD]).await?;
+
+    // 用户名密码认证
+    let auth_version = stream.read_u8().await?;
+    if auth_version != AUTH_VERSION {
+        bail!("unsupported SOCKS authentication version {}", auth_version);
+    }
+    let client_username = read_string(stream).await?;
+    let client_password = read_string(stream).await?;
+    if client_username != username || client_password != password {
+        stream.write_all(&[AUTH_VERSION, 0x01]).await?;
+        bail!("SOCKS authentication failed");
+    }
+    stream.write_all(&[AUTH_VERSION, 0x00]).await?;
+
+    // 读取请求
+    let mut request = [0u8; 4];
+    stream.read_exact(&mut request).await?;
+    if request[0] != VERSION {
+        bail!("unsupported SOCKS version {}", request[0]);
+    }
+    if request[1] != COMMAND_CONNECT {
+        reply(stream, Reply::CommandNotSupported).await?;
+        bail!("unsupported SOCKS command {}", request[1]);
+    }
+    let host = match request[3] {
+        ADDRESS_IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await?;
+            std::net::Ipv4Addr::from(octets).to_string()
+        }
+        ADDRESS_DOMAIN => read_string(stream).await?,
+        ADDRESS_IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await?;
+            std::net::Ipv6Addr::from(octets).to_string()
+        }
+        address_type => {
+            reply(stream, Reply::AddressTypeNotSupported).await?;
+            bail!("unsupported SOCKS address type {}", address_type);
+        }
+    };
+    let port = stream.read_u16().await?;
+    Ok((host, port))
+}
+
+/// 回复 CONNECT 请求，绑定地址固定为 0.0.0.0:0
+pub async fn reply<S>(stream: &mut S, reply: Reply) -> Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&[VERSION, reply as u8, 0x00, ADDRESS_IPV4, 0, 0, 0, 0, 0, 0])
+        .await?;
+    Ok(())
+}
+
+// 读取一个字节长度前缀的字符串
+async fn read_string<S>(stream: &mut S) -> Result<String>
+where
+    S: AsyncRead + Unpin,
+{
+    let len = stream.read_u8().await? as usize;
+    let mut bytes = vec![0u8; len];
+    stream.read_exact(&mut bytes).await?;
+    String::from_utf8(bytes).map_err(|_| anyhow!("invalid SOCKS string"))
+}
@@ -0,0 +1,299 @@
+use super::server_key::ServerKeyCheck;
+use super::ssh_mongo::{decode_private_key, SshCredential, SshHop};
+use crate::{utils::crypt::decrypt, SSH_KEY_FILE};
+use anyhow::{anyhow, Context, Result};
+use log::{info, warn};
+use russh::client::{self, Handle, Handler, KeyboardInteractiveAuthResponse, Prompt};
+use russh::keys::PrivateKeyWithHashAlg;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Listener};
+use tokio::sync::{oneshot, watch};
+use tokio::time::{sleep_until, timeout, Duration, Instant};
+
+// SSH 连接与认证
+// 直接使用 russh 建立连接，以便在 keyboard-interactive 认证时通过事件向前台询问验证码等输入。
+
+// 等待前台回答 keyboard-interactive 提示的时间
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+// 每一跳建立连接、握手和认证的超时时间，等待前台确认或输入的时间不计入
+const HOP_TIMEOUT: Duration = Duration::from_secs(15);
+
+// 连接建立后，超过该时间没有收到数据时发送 keepalive，连续 3 次无响应即断开
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// 超过该时间没有收到任何数据时断开连接
+const INACTIVITY_TIMEOUT: Duration = Duration::from_secs(180);
+
+// 询问前台的请求编号，回答事件名带上编号，多个连接同时询问时不会串用回答
+static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 生成询问前台的请求编号
+pub fn next_request_id() -> u64 {
+    REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 记录等待前台确认或输入的时间，连接超时计时时扣除
+#[derive(Clone)]
+pub struct PromptClock(watch::Sender<PromptState>);
+
+#[derive(Default)]
+struct PromptState {
+    // 正在等待前台时的开始时间
+    since: Option<Instant>,
+    // 已经结束的等待累计时间
+    waited: Duration,
+}
+
+impl Default for PromptClock {
+    fn default() -> Self {
+        Self(watch::Sender::new(PromptState::default()))
+    }
+}
+
+impl PromptClock {
+    /// 等待前台确认或输入，期间暂停连接超时计时
+    pub async fn pause<F: Future>(&self, future: F) -> F::Output {
+        self.0.send_modify(|state| state.since = Some(Instant::now()));
+        let output = future.await;
+        self.0.send_modify(|state| {
+            if let Some(since) = state.since.take() {
+                state.waited += since.elapsed();
+            }
+        });
+        output
+    }
+
+    /// 在 `limit` 内完成 `future`，等待前台的时间不计入
+    pub async fn timeout<F: Future>(&self, limit: Duration, future: F) -> Option<F::Output> {
+        let start = Instant::now();
+        let mut changed = self.0.subscribe();
+        tokio::pin!(future);
+        loop {
+            // 正在等待前台时不会超时，等待结束后再重新计算截止时间
+            let deadline = {
+                let state = changed.borrow_and_update();
+                state.since.is_none().then(|| start + limit + state.waited)
+            };
+            tokio::select! {
+                output = &mut future => return Some(output),
+                _ = changed.changed() => {}
+                _ = sleep_until(deadline.unwrap_or(start)), if deadline.is_some() => return None,
+            }
+        }
+    }
+}
+
+// 事件发送数据结构，keyboard-interactive 认证时发送给前台
+#[derive(Serialize, Clone)]
+struct KeyboardInteractiveData {
+    // 请求编号，前台以 `KeyboardInteractiveResponded-{id}` 事件回答
+    id: u64,
+    host: String,
+    name: String,
+    instructions: String,
+    prompts: Vec<PromptData>,
+}
+
+#[derive(Serialize, Clone)]
+struct PromptData {
+    prompt: String,
+    echo: bool,
+}
+
+/// 建立 SSH 连接并认证
+/// - `key_id` 为服务器公钥的保存键，已保存时比对，首次连接时在握手中询问前台是否信任
+/// - `via` 为上一跳的连接，多跳时经其转发，主机名由上一跳解析
+pub async fn connect(
+    hop: &SshHop,
+    key_id: &str,
+    via: Option<&Handle<ServerKeyCheck>>,
+    app_handle: AppHandle,
+) -> Result<Handle<ServerKeyCheck>> {
+    let (host, port) = (hop.host.as_str(), hop.port);
+    let saved_key = decrypt(key_id, app_handle.clone(), SSH_KEY_FILE)
+        .ok()
+        .filter(|key| !key.is_empty());
+    let clock = PromptClock::default();
+    let handler = ServerKeyCheck::new(key_id, host, port, saved_key, clock.clone(), app_handle.clone());
+
+    let config = Arc::new(client::Config {
+        inactivity_timeout: Some(INACTIVITY_TIMEOUT),
+        keepalive_interval: Some(KEEPALIVE_INTERVAL),
+        ..Default::default()
+    });
+    // 连接、握手和认证分别计时，主机不可达时不会一直等待
+    let handle = clock
+        .timeout(HOP_TIMEOUT, async {
+            match via {
+                None => client::connect(config, (host, port), handler).await,
+                Some(via) => {
+                    let channel = via
+                        .channel_open_direct_tcpip(host, port as u32, "127.0.0.1", 0)
+                        .await
+                        .map_err(|e| anyhow!("SSH failed to open channel to {}:{}: {}", host, port, e))?;
+                    client::connect_stream(config, channel.into_stream(), handler).await
+                }
+            }
+        })
+        .await
+        .ok_or_else(|| anyhow!("SSH connection to {}:{} timed out", host, port))?;
+    // 公钥校验的错误原样返回，前台据代号处理
+    let mut handle = handle.map_err(|e| match e.downcast::<russh::Error>() {
+        Ok(e) => anyhow!("SSH failed to connect to {}:{}: {}", host, port, e),
+        Err(e) => e,
+    })?;
+    clock
+        .timeout(
+            HOP_TIMEOUT,
+            authenticate(&mut handle, host, &hop.username, &hop.credential, &clock, app_handle),
+        )
+        .await
+        .ok_or_else(|| anyhow!("SSH authentication on {}:{} timed out", host, port))??;
+    Ok(handle)
+}
+
+/// SSH 认证，支持密码、私钥和 keyboard-interactive
+/// - keyboard-interactive 的提示通过 `KeyboardInteractive` 事件发送给前台，
+///   等待带请求编号的 `KeyboardInteractiveResponded-{id}` 事件返回回答
+/// - 一轮提示全部是密码提示且保存了密码时自动回答，不打扰前台
+/// - 等待前台回答的时间记入 `clock`，不计入认证超时
+pub async fn authenticate<H: Handler>(
+    handle: &mut Handle<H>,
+    host: &str,
+    user: &str,
+    credential: &SshCredential,
+    clock: &PromptClock,
+    app_handle: AppHandle,
+) -> Result<()> {
+    let success = match credential {
+        SshCredential::Password(password) => handle.authenticate_password(user, password).await?.success(),
+        SshCredential::PrivateKey { key, passphrase } => {
+            let private_key = decode_private_key(key, passphrase.as_deref())?;
+            let hash_alg = handle.best_supported_rsa_hash().await?.flatten();
+            handle
+                .authenticate_publickey(user, PrivateKeyWithHashAlg::new(Arc::new(private_key), hash_alg))
+                .await?
+                .success()
+        }
+        SshCredential::KeyboardInteractive { password } => {
+            let mut response = handle
+                .authenticate_keyboard_interactive_start(user, None::<String>)
+                .await?;
+            loop {
+                match response {
+                    KeyboardInteractiveAuthResponse::Success => break true,
+                    KeyboardInteractiveAuthResponse::Failure { .. } => break false,
+                    KeyboardInteractiveAuthResponse::InfoRequest {
+                        name,
+                        instructions,
+                        prompts,
+                    } => {
+                        let answers = answer_prompts(
+                            host,
+                            name,
+                            instructions,
+                            prompts,
+                            password.as_deref(),
+                            clock,
+                            &app_handle,
+                        )
+                        .await?;
+                        response = handle
+                            .authenticate_keyboard_interactive_respond(answers)
+                            .await?;
+                    }
+                }
+            }
+        }
+    };
+    if !success {
+        warn!("SSH authentication failed: {}@{}", user, host);
+        return Err(anyhow!("SSH authentication failed"));
+    }
+    info!("SSH authentication succeeded: {}@{}", user, host);
+    Ok(())
+}
+
+// 回答一轮 keyboard-interactive 提示，只有无法自动回答时才询问前台
+async fn answer_prompts(
+    host: &str,
+    name: String,
+    instructions: String,
+    prompts: Vec<Prompt>,
+    password: Option<&str>,
+    clock: &PromptClock,
+    app_handle: &AppHandle,
+) -> Result<Vec<String>> {
+    if let Some(answers) = auto_answers(&prompts, password) {
+        return Ok(answers);
+    }
+
+    let id = next_request_id();
+    let (answer_tx, answer_rx) = oneshot::channel();
+    // 先注册一次性监听，再发送事件，避免错过前台的回答
+    let event_id = app_handle.once(format!("KeyboardInteractiveResponded-{}", id), move |event| {
+        let _ = answer_tx.send(event.payload().to_string());
+    });
+    app_handle
+        .emit(
+            "KeyboardInteractive",
+            KeyboardInteractiveData {
+                id,
+                host: host.to_string(),
+                name,
+                instructions,
+                prompts: prompts
+                    .iter()
+                    .map(|prompt| PromptData {
+                        prompt: prompt.prompt.clone(),
+                        echo: prompt.echo,
+                    })
+                    .collect(),
+            },
+        )
+        .context("Failed to emit event")?;
+
+    let payload = match clock.pause(timeout(PROMPT_TIMEOUT, answer_rx)).await {
+        Ok(Ok(payload)) => payload,
+        _ => {
+            app_handle.unlisten(event_id);
+            return Err(anyhow!("keyboard-interactive prompt timed out"));
+        }
+    };
+    // 前台取消时返回 null
+    let answers: Option<Vec<String>> = serde_json::from_str(&payload)?;
+    let answers = answers.ok_or_else(|| anyhow!("keyboard-interactive authentication cancelled"))?;
+    if answers.len() != prompts.len() {
+        return Err(anyhow!(
+            "expected {} answers but got {}",
+            prompts.len(),
+            answers.len()
+        ));
+    }
+    Ok(answers)
+}
+
+/// 不需要询问前台时直接给出回答
+/// - 没有提示时回答为空
+/// - 全部提示都是密码提示且保存了密码时使用保存的密码
+pub fn auto_answers(prompts: &[Prompt], password: Option<&str>) -> Option<Vec<String>> {
+    let password = match password {
+        Some(password) => password,
+        None if prompts.is_empty() => return Some(Vec::new()),
+        None => return None,
+    };
+    prompts
+        .iter()
+        .map(|prompt| {
+            prompt
+                .prompt
+                .to_lowercase()
+                .contains("password")
+                .then(|| password.to_string())
+        })
+        .collect()
+}
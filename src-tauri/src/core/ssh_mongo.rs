@@ -1,30 +1,168 @@
-use crate::SSH_KEY_FILE;
-use crate::utils::crypt::decrypt;
-use super::server_key::key_check;
-use async_ssh2_tokio::client::{AuthMethod, Client, ServerCheckMethod};
+use super::uri::ConnectionProfile;
+use super::server_key::ServerKeyCheck;
+use super::socks::{self, Reply};
+use super::tls::create_client;
+use super::ssh_client::connect;
 use log::{error, info, warn, debug};
-use mongodb::{bson, options::ClientOptions, Client as MongoClient, Database};
-use std::net::SocketAddr;
+use mongodb::{bson, options::Socks5Proxy, Client as MongoClient, Database};
+use rand_core::{OsRng, TryRngCore};
+use russh::client::Handle;
+use std::net::Ipv4Addr;
 use tauri::AppHandle;
 use tokio::io::{copy_bidirectional, AsyncWriteExt};
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 use tokio::task::{JoinHandle, AbortHandle};
 use tokio::time::{sleep, Duration, timeout};
 use anyhow::{anyhow, Result};
+use russh::keys::{decode_secret_key, PrivateKey};
+use serde_json::Value;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+// 本地 SOCKS5 入口等待客户端完成协商的时间
+const SOCKS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
 
-// 本地应用 → 本地端口 → SSH隧道循环 → SSH连接 → 远程MongoDB
-//     │          │           │           │
-//     │          │           ├─ 接受新连接 → 创建转发任务
-//     │          │           │
-//     │          │           └─ 监听终止信号 → 清理所有连接
-//     │          │
+
+// MongoDB驱动 → 本地SOCKS5入口 → SSH隧道循环 → SSH连接 → 请求的MongoDB节点
+//     │               │               │
+//     │               │               ├─ 接受新连接 → SOCKS5认证 → 创建转发任务
+//     │               │               │
+//     │               │               └─ 监听终止信号 → 清理所有连接
+//     │               │
 //     └─ 通信保持 ←─ 独立转发任务处理每个连接
 
 
+// SSH 认证信息，与 MongoDB 密码一起加密保存
+#[derive(Clone)]
+pub enum SshCredential {
+    Password(String),
+    // OpenSSH 或 PEM 格式的私钥（ed25519、RSA、ECDSA），口令可选
+    PrivateKey {
+        key: String,
+        passphrase: Option<String>,
+    },
+    // 由服务器逐轮提示输入，如密码加动态验证码，密码可选
+    KeyboardInteractive {
+        password: Option<String>,
+    },
+}
+
+impl SshCredential {
+    /// 从解密后的连接配置中读取 SSH 认证信息
+    /// - `sshAuthMethod` 为 `key` 时使用 `sshPrivateKey` 和 `sshPassphrase`
+    /// - `sshAuthMethod` 为 `keyboardInteractive` 时 `sshPassword` 可以为空
+    /// - 其余情况使用 `sshPassword`
+    pub fn from_secrets(secrets: &Value) -> Result<SshCredential> {
+        let auth_method = secrets["sshAuthMethod"].as_str();
+        if auth_method == Some("keyboardInteractive") {
+            let password = secrets["sshPassword"]
+                .as_str()
+                .filter(|password| !password.is_empty());
+            Ok(SshCredential::KeyboardInteractive {
+                password: password.map(str::to_string),
+            })
+        } else if auth_method == Some("key") {
+            let key = secrets["sshPrivateKey"]
+                .as_str()
+                .filter(|key| !key.trim().is_empty())
+                .ok_or_else(|| anyhow!("sshPrivateKey is empty"))?;
+            let passphrase = secrets["sshPassphrase"]
+                .as_str()
+                .filter(|passphrase| !passphrase.is_empty());
+            Ok(SshCredential::PrivateKey {
+                key: key.to_string(),
+                passphrase: passphrase.map(str::to_string),
+            })
+        } else {
+            let password = secrets["sshPassword"]
+                .as_str()
+                .ok_or_else(|| anyhow!("sshPassword is empty"))?;
+            Ok(SshCredential::Password(password.to_string()))
+        }
+    }
+
+    /// 检查私钥格式和口令，密码认证无需检查
+    pub fn validate(&self) -> Result<()> {
+        if let SshCredential::PrivateKey { key, passphrase } = self {
+            decode_private_key(key, passphrase.as_deref())?;
+        }
+        Ok(())
+    }
+}
+
+/// 解码 OpenSSH 或 PEM 格式的私钥
+pub fn decode_private_key(key: &str, passphrase: Option<&str>) -> Result<PrivateKey> {
+    decode_secret_key(key, passphrase)
+        .map_err(|e| anyhow!("invalid SSH private key or passphrase: {}", e))
+}
+
+// SSH 跳板机，多跳时每一跳都经上一跳的 direct-tcpip 通道连接
+#[derive(Clone)]
+pub struct SshHop {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub credential: SshCredential,
+}
+
+impl SshHop {
+    /// 从解密后的连接配置中读取全部跳板机，按连接顺序排列
+    /// - 第一跳为连接配置中的 SSH 主机，之后依次为 `sshHops` 中的跳板机
+    /// - `sshHops` 中每一跳的字段名与第一跳相同
+    /// - 最后一跳负责转发到 MongoDB
+    pub fn chain_from_secrets(
+        ssh_host: &str,
+        ssh_port: u16,
+        ssh_username: &str,
+        secrets: &Value,
+    ) -> Result<Vec<SshHop>> {
+        let mut hops = vec![SshHop {
+            host: ssh_host.to_string(),
+            port: ssh_port,
+            username: ssh_username.to_string(),
+            credential: SshCredential::from_secrets(secrets)?,
+        }];
+        let empty = Vec::new();
+        for (index, hop) in secrets["sshHops"].as_array().unwrap_or(&empty).iter().enumerate() {
+            let host = hop["sshHost"]
+                .as_str()
+                .filter(|host| !host.is_empty())
+                .ok_or_else(|| anyhow!("sshHost of hop {} is empty", index + 1))?;
+            let port = match &hop["sshPort"] {
+                Value::Null => 22,
+                // 前台数字输入框可能保存为字符串
+                Value::String(port) => port.parse()?,
+                port => port
+                    .as_u64()
+                    .and_then(|port| u16::try_from(port).ok())
+                    .ok_or_else(|| anyhow!("invalid sshPort of hop {}", index + 1))?,
+            };
+            let username = hop["sshUsername"]
+                .as_str()
+                .ok_or_else(|| anyhow!("sshUsername of hop {} is empty", index + 1))?;
+            hops.push(SshHop {
+                host: host.to_string(),
+                port,
+                username: username.to_string(),
+                credential: SshCredential::from_secrets(hop)?,
+            });
+        }
+        Ok(hops)
+    }
+}
+
+/// 第 `index` 跳服务器公钥的保存键
+/// - 第一跳沿用连接 ID
+/// - 之后的跳板机按主机和端口区分，增删或调整跳板机顺序后已信任的公钥仍然对应原来的主机
+pub fn hop_key_id(id: &str, index: usize, hop: &SshHop) -> String {
+    if index == 0 {
+        id.to_string()
+    } else {
+        format!("{}:{}:{}", id, hop.host, hop.port)
+    }
+}
+
 // SSH隧道生命周期结构体
 pub struct SshTunnelManager {
     tunnel_handle: AbortHandle,      // 隧道任务中止句柄
@@ -62,78 +200,22 @@ impl Drop for SshTunnelManager {
 }
 
 /// 启动SSH隧道
-/// 1. 建立SSH连接到跳板机
-/// 2. 绑定本地端口
-/// 3. 循环接受本地连接并转发到远程MongoDB
-/// 4. 监听终止信号，清理资源
+/// 1. 循环接受本地 SOCKS5 入口的连接
+/// 2. 完成 SOCKS5 认证后，通过最后一跳的 SSH 连接打开到请求节点的 direct-tcpip 通道并双向转发
+/// 3. 监听终止信号，清理资源
+/// - `sessions` 为各跳已认证的连接，需要在隧道运行期间全部保持
 async fn start_ssh_tunnel(
-    ssh_host: String,
-    ssh_port: u16,
-    ssh_user: String,
-    auth_method: AuthMethod,
-    server_check_method: ServerCheckMethod,
-    remote_mongo_addr: SocketAddr,
+    sessions: Vec<Handle<ServerKeyCheck>>,
+    listener: TcpListener,
+    credentials: ProxyCredentials,
     mut shutdown_rx: mpsc::Receiver<()>,
-    success_tx: mpsc::Sender<SocketAddr>, // 成功时发送实际绑定的地址
     active_connections: Arc<Mutex<Vec<JoinHandle<()>>>>,
 ) -> Result<()> {
-    // 建立 SSH 连接到跳板机
-    let client = match Client::connect(
-        (ssh_host.as_str(), ssh_port),
-        ssh_user.as_str(),
-        auth_method,
-        server_check_method,
-    )
-    .await
-    {
-        Ok(client) => {
-            info!("SSH has been established: {}@{}:{}", ssh_user, ssh_host, ssh_port);
-            client
-        }
-        Err(e) => {
-            return Err(anyhow!("SSH failed to connect: {}", e));
-        }
-    };
-
-    // 本地监听端口（重试逻辑）
-    let mut current_port = 27017; // 默认MongoDB端口
-    let max_retries = 20; // 最多重试20次
-    let mut listener = None;
-
-    for i in 0..=max_retries {
-        let addr = SocketAddr::new("127.0.0.1".parse().unwrap(), current_port);
-        match TcpListener::bind(addr).await {
-            Ok(l) => {
-                listener = Some((l, addr));
-                info!("local port forwarding has been started: {} -> {}", addr, remote_mongo_addr);
-                break;
-            }
-            Err(e) if i < max_retries => {
-                warn!(
-                    "port {} is ocupied ({}), trying next port: {}",
-                    current_port,
-                    e,
-                    current_port + 1
-                );
-                current_port += 1;
-            }
-            Err(e) => {
-                return Err(anyhow!(
-                    "try {} times but cant't bind port: {}",
-                    max_retries + 1,
-                    e
-                ));
-            }
-        }
-    }
-
-    let (listener, local_bind_addr) = listener.expect("can't bind local port");
-    
-    // 发送成功信号，包含实际绑定的地址
-    if let Err(e) = success_tx.send(local_bind_addr).await {
-        error!("failed to send success signal: {}", e);
-        return Err(anyhow!("failed to send success signal: {}", e));
+    if sessions.is_empty() {
+        return Err(anyhow!("no SSH host is configured"));
     }
+    let sessions = Arc::new(sessions);
+    let credentials = Arc::new(credentials);
 
     // 循环接受本地连接，同时监听终止信号
     loop {
@@ -147,35 +229,23 @@ async fn start_ssh_tunnel(
                         continue;
                     }
                 };
-                
-                debug!("new local connection: {:?}", local_stream.peer_addr());
 
-				// 通过SSH创建到远程MongoDB的通道
-                let ssh_channel = match client
-                    .open_direct_tcpip_channel(remote_mongo_addr, None)
-                    .await
-                {
-                    Ok(channel) => channel,
-                    Err(e) => {
-                        error!("failed to create ssh channel: {}", e);
-                        continue;
-                    }
-                };
+                debug!("new local connection: {:?}", local_stream.peer_addr());
 
-                let mut ssh_stream = ssh_channel.into_stream();
-                
-                // y异步创建连接任务
+                // 异步处理每个连接
+                let sessions = Arc::clone(&sessions);
+                let credentials = Arc::clone(&credentials);
                 let handle = tokio::spawn(async move {
-					// 在两者之间双向通信复制数据
-                     let _ = copy_bidirectional(&mut local_stream, &mut ssh_stream).await;
-                    
-                    // 确保连接被正确关闭
-                    let _ = local_stream.shutdown().await;
-                    let _ = ssh_stream.shutdown().await;
+                    let ssh = sessions.last().expect("checked above");
+                    if let Err(e) = forward_connection(&mut local_stream, ssh, &credentials).await {
+                        warn!("SSH tunnel connection failed: {}", e);
+                    }
                 });
-                
-                // 存储连接句柄
-                active_connections.lock().await.push(handle);
+
+                // 存储连接句柄，顺带移除已结束的连接
+                let mut connections = active_connections.lock().await;
+                connections.retain(|handle| !handle.is_finished());
+                connections.push(handle);
             }
             // 收到终止信号，退出循环
             _ = shutdown_rx.recv() => {
@@ -191,150 +261,168 @@ async fn start_ssh_tunnel(
     }
 }
 
+// 本地 SOCKS5 入口的认证信息，每条隧道随机生成
+struct ProxyCredentials {
+    username: String,
+    password: String,
+}
+
+impl ProxyCredentials {
+    fn generate() -> Result<Self> {
+        Ok(Self {
+            username: random_hex()?,
+            password: random_hex()?,
+        })
+    }
+}
+
+fn random_hex() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    OsRng
+        .try_fill_bytes(&mut bytes)
+        .map_err(|e| anyhow!("failed to generate proxy credentials: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+// 处理一个本地连接：SOCKS5 认证后经 SSH 打开到目标节点的通道并双向转发
+// 目标主机名交给最后一跳解析，内网主机名在本地无法解析
+async fn forward_connection(
+    local_stream: &mut TcpStream,
+    ssh: &Handle<ServerKeyCheck>,
+    credentials: &ProxyCredentials,
+) -> Result<()> {
+    let (host, port) = timeout(
+        SOCKS_HANDSHAKE_TIMEOUT,
+        socks::accept_request(local_stream, &credentials.username, &credentials.password),
+    )
+    .await
+    .map_err(|_| anyhow!("SOCKS handshake timed out"))??;
+
+    // 通过SSH创建到远程MongoDB的通道
+    let ssh_channel = match ssh
+        .channel_open_direct_tcpip(host.as_str(), port as u32, "127.0.0.1", 0)
+        .await
+    {
+        Ok(channel) => channel,
+        Err(e) => {
+            socks::reply(local_stream, Reply::HostUnreachable).await?;
+            return Err(anyhow!("failed to open SSH channel to {}:{}: {}", host, port, e));
+        }
+    };
+    socks::reply(local_stream, Reply::Succeeded).await?;
+    debug!("SSH tunnel forwarding to {}:{}", host, port);
+
+    // 在两者之间双向通信复制数据
+    let mut ssh_stream = ssh_channel.into_stream();
+    let _ = copy_bidirectional(local_stream, &mut ssh_stream).await;
+
+    // 确保连接被正确关闭
+    let _ = local_stream.shutdown().await;
+    let _ = ssh_stream.shutdown().await;
+    Ok(())
+}
+
 /// 通过SSH隧道连接到MongoDB
-/// 1. 解密或获取SSH密钥
-/// 2. 启动SSH隧道
-/// 3. 构建MongoDB连接字符串并测试连接
+/// 1. 依次连接各跳 SSH 主机，握手时校验服务器公钥（首次连接时询问前台是否信任），使用密码、私钥或 keyboard-interactive 认证
+/// 2. 在本地回环地址启动 SOCKS5 入口，经最后一跳转发到任意节点
+/// 3. 驱动通过 SOCKS5 代理以真实主机名连接各节点，自行发现拓扑、按读偏好选择节点并在主从切换时故障转移
 /// 4. 返回MongoDB客户端、数据库和隧道管理器
 pub async fn ssh_tunnel_to_mongodb(
     id: &str,
-    mongo_auth_method: &str,
-    ssh_host: &str,
-    ssh_port: u16,
-    ssh_user: &str,
-    ssh_password: &str,
-    remote_mongo: &str,
-    mongo_user: &str,
-    mongo_password: &str,
-    db_name: &str,
+    hops: &[SshHop],
+    profile: &ConnectionProfile,
     app_handle: AppHandle,
 ) -> Result<(MongoClient, Database, Option<SshTunnelManager>)> {
-    let public_key = match decrypt(id, app_handle.clone(), SSH_KEY_FILE) {
-        Ok(decrypt_key) if !decrypt_key.is_empty() => {
-            // 直接使用存储的密钥
-            if let Some(key) = decrypt_key.split_whitespace().nth(1) {
-                key.to_string()
-            } else {
-                return Err(anyhow!("SSH key format error".to_string()));
-            }
-        }
-        _ => {
-            // 首次连接，需要获取并验证密钥
-            let key = key_check(
-                id,
-                ssh_host,
-                ssh_port,
-                ssh_user,
-                ssh_password,
-                app_handle.clone(),
-            )
-            .await?;
-
-			// 第一次连接密钥保存后返回为空，图省事以特定代号告诉前台不是错误，重新连接即可
-            if key.is_empty() {
-                return Err(anyhow::anyhow!("Code-3581"));
-            }
-            key
-        }
-    };
+    // SRV 记录只能在本地解析，隧道另一侧的 DNS 不可用
+    if profile.options.srv {
+        return Err(anyhow!("mongodb+srv:// is not supported over SSH"));
+    }
+    // 提前检查私钥，避免口令错误时只得到笼统的认证失败
+    for hop in hops {
+        hop.credential.validate()?;
+    }
 
-    let remote_mongo_addr: SocketAddr = remote_mongo
-        .parse()
-        .map_err(|e| anyhow!("remote MongoDB address parse failed: {}", e))?;
+    // 在启动隧道前依次连接各跳，首次连接时等待前台确认公钥
+    let mut sessions: Vec<Handle<ServerKeyCheck>> = Vec::new();
+    for (index, hop) in hops.iter().enumerate() {
+        let session = connect(hop, &hop_key_id(id, index, hop), sessions.last(), app_handle.clone()).await?;
+        info!("SSH has been established: {}@{}:{}", hop.username, hop.host, hop.port);
+        sessions.push(session);
+    }
 
-    // 创建认证方法
-    let auth_method = AuthMethod::with_password(ssh_password);
-    let server_check_method = ServerCheckMethod::with_public_key(&public_key);
+    // 本地 SOCKS5 入口只监听回环地址，端口由系统分配
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+    let local_addr = listener.local_addr()?;
+    let credentials = ProxyCredentials::generate()?;
+    let proxy = Socks5Proxy::builder()
+        .host(local_addr.ip().to_string())
+        .port(local_addr.port())
+        .authentication((credentials.username.clone(), credentials.password.clone()))
+        .build();
 
     // 创建信号通道
     let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
-    let (error_tx, mut error_rx) = mpsc::channel(1);
-    let (success_tx, mut success_rx) = mpsc::channel(1);
-    
+
     // 活动连接列表
     let active_connections = Arc::new(Mutex::new(Vec::new()));
 
     // 在后台启动SSH隧道
     let tunnel_handle = tokio::spawn({
-        let ssh_host = ssh_host.to_string();
-        let ssh_user = ssh_user.to_string();
         let active_connections = Arc::clone(&active_connections);
-        
         async move {
             if let Err(e) = start_ssh_tunnel(
-                ssh_host,
-                ssh_port,
-                ssh_user,
-                auth_method,
-                server_check_method,
-                remote_mongo_addr,
+                sessions,
+                listener,
+                credentials,
                 shutdown_rx,
-                success_tx,
                 active_connections,
             )
             .await
             {
-                error!("SSH tunnel start failed: {}", e);
-                let _ = error_tx.send(e).await;
+                error!("SSH tunnel stopped: {}", e);
             }
         }
     }).abort_handle();
 
-    // 等待隧道启动成功或失败，设置20秒超时
-    let local_bind_addr = match timeout(Duration::from_secs(20), async {
-        tokio::select! {
-            Some(addr) = success_rx.recv() => Ok(addr),
-            Some(e) = error_rx.recv() => Err(e),
-        }
-    })
-    .await
-    {
-        Ok(Ok(addr)) => addr,
-        Ok(Err(e)) => {
+    info!("SSH tunnel has been started, SOCKS5 proxy: {}", local_addr);
+
+    // 连接MongoDB
+    let client = match connect_through_proxy(profile, proxy).await {
+        Ok(client) => client,
+        Err(e) => {
             tunnel_handle.abort();
             return Err(e);
         }
-        Err(_) => {
-            tunnel_handle.abort();
-            return Err(anyhow!("SSH tunnel start timeout".to_string()));
-        }
     };
+    let db = client.database(&profile.db_name);
 
-    info!("SSH tunnel has been started, local address: {}", local_bind_addr);
-
-    // 构建MongoDB连接字符串
-    let mongo_uri = if mongo_auth_method != "userpass" {
-        format!("mongodb://{}/{}", local_bind_addr, db_name)
-    } else {
-        format!(
-            "mongodb://{}:{}@{}/{}",
-            mongo_user, mongo_password, local_bind_addr, db_name
-        )
+    // 创建隧道管理器
+    let tunnel_manager = SshTunnelManager {
+        tunnel_handle,
+        shutdown_tx,
+        active_connections,
     };
 
-    // 连接MongoDB
-    let client_options = ClientOptions::parse(&mongo_uri)
+    Ok((client, db, Some(tunnel_manager)))
+}
+
+// 通过 SOCKS5 代理连接并测试连接，主机名、副本集等选项与直连时相同
+async fn connect_through_proxy(profile: &ConnectionProfile, proxy: Socks5Proxy) -> Result<MongoClient> {
+    let mut client_options = profile
+        .client_options()
         .await
-        .map_err(|e| anyhow!("MongoDB URI parse failed: {}", e))?;
-        
-    let client = MongoClient::with_options(client_options)
+        .map_err(|e| anyhow!("MongoDB options parse failed: {}", e))?;
+    client_options.server_selection_timeout = Some(Duration::from_secs(15));
+    client_options.socks5_proxy = Some(proxy);
+
+    let client = create_client(client_options, &profile.tls_certificate_key, &profile.tls_ca_certificate)
         .map_err(|e| anyhow!("MongoDB client create failed: {}", e))?;
-        
+
     // 测试连接
     client
         .database("admin")
         .run_command(bson::doc! {"ping": 1})
         .await
         .map_err(|e| anyhow!("MongoDB connect test failed: {}", e))?;
-
-    let db = client.database(db_name);
-    
-    // 创建隧道管理器
-    let tunnel_manager = SshTunnelManager {
-        tunnel_handle,
-        shutdown_tx,
-        active_connections,
-    };
-
-    Ok((client, db, Some(tunnel_manager)))
-}
\ No newline at end of file
+    Ok(client)
+}
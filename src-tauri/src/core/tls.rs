@@ -0,0 +1,106 @@
+use anyhow::{anyhow, bail, Result};
+use log::warn;
+use mongodb::{
+    options::{ClientOptions, Tls, TlsOptions},
+    Client,
+};
+use rand_core::{OsRng, TryRngCore};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+// TLS 客户端证书和 CA 证书
+// 驱动只能从文件读取证书和私钥，而连接配置中的证书是加密保存的，
+// 因此创建 Client 时写入仅当前用户可读的临时文件，驱动在创建 Client 时读取完毕后立即删除。
+
+/// 不校验服务器证书的主机名
+/// - 只有 OpenSSL 后端支持，需以 `openssl-tls` 特性编译；默认的 rustls 后端返回错误
+#[cfg(feature = "openssl-tls")]
+pub fn allow_invalid_hostnames(client_options: &mut ClientOptions) -> Result<()> {
+    match client_options.tls.as_mut() {
+        Some(Tls::Enabled(tls_options)) => {
+            tls_options.allow_invalid_hostnames = Some(true);
+            Ok(())
+        }
+        _ => bail!("tlsAllowInvalidHostnames requires TLS to be enabled"),
+    }
+}
+
+#[cfg(not(feature = "openssl-tls"))]
+pub fn allow_invalid_hostnames(_client_options: &mut ClientOptions) -> Result<()> {
+    bail!("tlsAllowInvalidHostnames is not supported by this build")
+}
+
+/// 创建 Client，`certificate_key` 非空时作为客户端证书使用，`ca_certificate` 非空时作为 CA 证书使用
+/// - `certificate_key` 为 PEM 格式，包含证书和私钥
+/// - `ca_certificate` 为 PEM 格式的 CA 证书，可以包含多个证书
+/// - 使用客户端证书或 CA 证书必须开启 TLS
+pub fn create_client(
+    mut client_options: ClientOptions,
+    certificate_key: &str,
+    ca_certificate: &str,
+) -> Result<Client> {
+    let certificate_key = Some(certificate_key).filter(|pem| !pem.trim().is_empty());
+    let ca_certificate = Some(ca_certificate).filter(|pem| !pem.trim().is_empty());
+    if certificate_key.is_none() && ca_certificate.is_none() {
+        return Ok(Client::with_options(client_options)?);
+    }
+    let tls_options = match client_options.tls.as_mut() {
+        Some(Tls::Enabled(tls_options)) => tls_options,
+        _ if certificate_key.is_some() => bail!("a client certificate requires TLS to be enabled"),
+        _ => bail!("a CA certificate requires TLS to be enabled"),
+    };
+    let mut paths = Vec::new();
+    let result = write_certificates(tls_options, certificate_key, ca_certificate, &mut paths)
+        .and_then(|()| Ok(Client::with_options(client_options)?));
+    for path in paths {
+        if let Err(e) = fs::remove_file(&path) {
+            warn!("failed to remove {}: {}", path.display(), e);
+        }
+    }
+    result
+}
+
+// 写入证书临时文件并设置到 TLS 选项，写入的文件记录在 `paths` 中，创建 Client 后删除
+fn write_certificates(
+    tls_options: &mut TlsOptions,
+    certificate_key: Option<&str>,
+    ca_certificate: Option<&str>,
+    paths: &mut Vec<PathBuf>,
+) -> Result<()> {
+    if let Some(certificate_key) = certificate_key {
+        let path = write_temp_pem(certificate_key)?;
+        paths.push(path.clone());
+        tls_options.cert_key_file_path = Some(path);
+    }
+    if let Some(ca_certificate) = ca_certificate {
+        // rustls 会静默忽略无法识别的内容，提前检查避免之后只得到笼统的证书校验失败
+        if !ca_certificate.contains("-----BEGIN CERTIFICATE-----") {
+            bail!("the CA certificate must be in PEM format");
+        }
+        let path = write_temp_pem(ca_certificate)?;
+        paths.push(path.clone());
+        tls_options.ca_file_path = Some(path);
+    }
+    Ok(())
+}
+
+// 写入随机命名的临时文件，Unix 下权限为 0600
+fn write_temp_pem(pem: &str) -> Result<PathBuf> {
+    let mut name = [0u8; 16];
+    OsRng
+        .try_fill_bytes(&mut name)
+        .map_err(|e| anyhow!("failed to generate file name: {}", e))?;
+    let path = std::env::temp_dir().join(format!("lomongo-{}.pem", hex::encode(name)));
+
+    let mut open_options = OpenOptions::new();
+    open_options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        open_options.mode(0o600);
+    }
+    let mut file = open_options.open(&path)?;
+    file.write_all(pem.as_bytes())?;
+    Ok(path)
+}
@@ -0,0 +1,494 @@
+use super::tls::allow_invalid_hostnames;
+use anyhow::{anyhow, bail};
+use mongodb::{
+    bson::Document,
+    options::{AuthMechanism, ClientOptions, Credential},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// 连接串（URI）的导入与导出
+// 支持 `mongodb://` 与 `mongodb+srv://` 两种格式，用户名、密码和数据库名按 RFC 3986 百分号编码。
+
+const DEFAULT_PORT: u16 = 27017;
+
+const READ_PREFERENCES: &[&str] = &[
+    "primary",
+    "primaryPreferred",
+    "secondary",
+    "secondaryPreferred",
+    "nearest",
+];
+
+// MongoDB 认证方式，与前台连接配置中的 `authMethod` 对应
+// - `none` 和 `userpass` 沿用早期保存的取值，`userpass` 由服务器协商 SCRAM 机制
+// - 其余取值即连接串中的 `authMechanism`
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MongoAuthMethod {
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    #[serde(rename = "userpass")]
+    UserPass,
+    #[serde(rename = "SCRAM-SHA-1")]
+    ScramSha1,
+    #[serde(rename = "SCRAM-SHA-256")]
+    ScramSha256,
+    // 使用 TLS 客户端证书认证，用户名可以省略，由服务器从证书中读取
+    #[serde(rename = "MONGODB-X509")]
+    X509,
+    // 用户名、密码为 AWS 访问密钥，省略时从环境变量或实例角色获取
+    #[serde(rename = "MONGODB-AWS")]
+    Aws,
+    // 仅支持机器认证流程，通过 authMechanismProperties 指定 ENVIRONMENT
+    #[serde(rename = "MONGODB-OIDC")]
+    Oidc,
+}
+
+impl MongoAuthMethod {
+    /// 连接串中的 `authMechanism`，`none` 和 `userpass` 不需要指定
+    pub fn mechanism(self) -> Option<&'static str> {
+        match self {
+            MongoAuthMethod::None | MongoAuthMethod::UserPass => None,
+            MongoAuthMethod::ScramSha1 => Some("SCRAM-SHA-1"),
+            MongoAuthMethod::ScramSha256 => Some("SCRAM-SHA-256"),
+            MongoAuthMethod::X509 => Some("MONGODB-X509"),
+            MongoAuthMethod::Aws => Some("MONGODB-AWS"),
+            MongoAuthMethod::Oidc => Some("MONGODB-OIDC"),
+        }
+    }
+
+    /// 由连接串中的 `authMechanism` 解析认证方式
+    pub fn from_mechanism(mechanism: &str) -> anyhow::Result<MongoAuthMethod> {
+        [
+            MongoAuthMethod::ScramSha1,
+            MongoAuthMethod::ScramSha256,
+            MongoAuthMethod::X509,
+            MongoAuthMethod::Aws,
+            MongoAuthMethod::Oidc,
+        ]
+        .into_iter()
+        .find(|method| method.mechanism() == Some(mechanism))
+        .ok_or_else(|| anyhow!("unsupported authMechanism '{}'", mechanism))
+    }
+
+    /// 是否使用用户名，X.509 和 AWS 的用户名可以为空
+    pub fn uses_username(self) -> bool {
+        !matches!(self, MongoAuthMethod::None | MongoAuthMethod::Oidc)
+    }
+
+    /// 是否需要从加密文件中读取密码
+    pub fn uses_password(self) -> bool {
+        matches!(
+            self,
+            MongoAuthMethod::UserPass
+                | MongoAuthMethod::ScramSha1
+                | MongoAuthMethod::ScramSha256
+                | MongoAuthMethod::Aws
+        )
+    }
+}
+
+// 连接配置中除主机、认证外的连接选项，与前台连接配置中的 `options` 对应
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ConnectionOptions {
+    // 使用 `mongodb+srv://` 通过 DNS 发现节点
+    pub srv: bool,
+    // 多个种子节点 `host:port`，为空时使用 mongo_host:mongo_port
+    pub hosts: Vec<String>,
+    pub replica_set: Option<String>,
+    pub auth_source: Option<String>,
+    // primary、primaryPreferred、secondary、secondaryPreferred 或 nearest
+    pub read_preference: Option<String>,
+    // 直接连接指定节点，不做拓扑发现
+    pub direct_connection: Option<bool>,
+    pub tls: bool,
+    // 自定义 CA 证书文件路径（PEM），来自导入的连接串
+    pub tls_ca_file: Option<String>,
+    // 是否保存了 CA 证书，PEM 加密保存在 `tlsCaCertificate` 中
+    pub tls_ca_certificate: bool,
+    // 是否保存了客户端证书，证书和私钥加密保存在 `tlsCertificateKey` 中
+    pub tls_client_certificate: bool,
+    // 不校验服务器证书中的主机名，需要 OpenSSL 后端
+    pub tls_allow_invalid_hostnames: bool,
+    // 其他连接串参数原样保留
+    pub params: BTreeMap<String, String>,
+}
+
+impl ConnectionOptions {
+    /// 副本集名称，空字符串视为未设置
+    pub fn replica_set_name(&self) -> Option<&str> {
+        non_empty(&self.replica_set)
+    }
+
+    /// 生成连接串的查询参数部分，不含开头的 `?`
+    /// - 前台清空的文本选项为空字符串，视为未设置
+    pub fn query_string(&self) -> String {
+        let mut pairs = Vec::new();
+        if let Some(replica_set) = self.replica_set_name() {
+            pairs.push(format!("replicaSet={}", percent_encode(replica_set)));
+        }
+        if let Some(auth_source) = non_empty(&self.auth_source) {
+            pairs.push(format!("authSource={}", percent_encode(auth_source)));
+        }
+        if let Some(read_preference) = non_empty(&self.read_preference) {
+            pairs.push(format!("readPreference={}", percent_encode(read_preference)));
+        }
+        if let Some(direct_connection) = self.direct_connection {
+            pairs.push(format!("directConnection={}", direct_connection));
+        }
+        // srv 默认启用 TLS，只有显式关闭时才需要写出
+        if self.tls != self.srv {
+            pairs.push(format!("tls={}", self.tls));
+        }
+        if let Some(ca_file) = non_empty(&self.tls_ca_file) {
+            pairs.push(format!("tlsCAFile={}", percent_encode(ca_file)));
+        }
+        if self.tls_allow_invalid_hostnames {
+            pairs.push("tlsAllowInvalidHostnames=true".to_string());
+        }
+        for (key, value) in &self.params {
+            pairs.push(format!("{}={}", percent_encode(key), percent_encode(value)));
+        }
+        pairs.join("&")
+    }
+}
+
+// 由连接串解析出的连接配置，字段与前台的连接配置一致
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionProfile {
+    #[serde(rename = "type")]
+    pub connect_type: String,
+    pub auth_method: MongoAuthMethod,
+    pub mongo_host: String,
+    pub mongo_port: u16,
+    pub mongo_username: String,
+    pub mongo_password: String,
+    pub db_name: String,
+    pub options: ConnectionOptions,
+    // 客户端证书和私钥（PEM），与密码一样加密保存，不写入连接串
+    #[serde(skip)]
+    pub tls_certificate_key: String,
+    // CA 证书（PEM），与客户端证书一样加密保存，不写入连接串
+    #[serde(skip)]
+    pub tls_ca_certificate: String,
+}
+
+impl ConnectionProfile {
+    /// 将连接配置导出为连接串
+    /// - `include_password` 为假时省略密码，得到的连接串可以安全地分享
+    pub fn to_uri(&self, include_password: bool) -> String {
+        let mut uri = String::from(if self.options.srv {
+            "mongodb+srv://"
+        } else {
+            "mongodb://"
+        });
+        if self.auth_method.uses_username() && !self.mongo_username.is_empty() {
+            uri.push_str(&percent_encode(&self.mongo_username));
+            if include_password && !self.mongo_password.is_empty() {
+                uri.push(':');
+                uri.push_str(&percent_encode(&self.mongo_password));
+            }
+            uri.push('@');
+        }
+        if self.options.srv {
+            uri.push_str(&self.mongo_host);
+        } else if !self.options.hosts.is_empty() {
+            uri.push_str(&self.options.hosts.join(","));
+        } else {
+            uri.push_str(&format_host(&self.mongo_host, self.mongo_port));
+        }
+        uri.push('/');
+        uri.push_str(&percent_encode(&self.db_name));
+        let mut query = self.options.query_string();
+        if let Some(mechanism) = self.auth_method.mechanism() {
+            query = if query.is_empty() {
+                format!("authMechanism={}", mechanism)
+            } else {
+                format!("authMechanism={}&{}", mechanism, query)
+            };
+        }
+        if !query.is_empty() {
+            uri.push('?');
+            uri.push_str(&query);
+        }
+        uri
+    }
+
+    /// 生成创建 Client 使用的连接选项
+    /// - 主机和连接选项由不含认证信息的连接串解析
+    /// - 用户名、密码等认证信息直接设置到 `Credential`，不经过连接串编码
+    /// - `tlsAllowInvalidHostnames` 需要 OpenSSL 后端，见 `tls::allow_invalid_hostnames`
+    pub async fn client_options(&self) -> anyhow::Result<ClientOptions> {
+        let mut anonymous = self.clone();
+        anonymous.auth_method = MongoAuthMethod::None;
+        anonymous.options.auth_source = None;
+        anonymous.options.tls_allow_invalid_hostnames = false;
+        let mechanism_properties = anonymous.options.params.remove("authMechanismProperties");
+        let mut client_options = ClientOptions::parse(anonymous.to_uri(false)).await?;
+        client_options.credential = self.credential(mechanism_properties.as_deref())?;
+        if self.options.tls_allow_invalid_hostnames {
+            allow_invalid_hostnames(&mut client_options)?;
+        }
+        Ok(client_options)
+    }
+
+    // 按认证方式生成 Credential，无需认证时返回 None
+    fn credential(&self, mechanism_properties: Option<&str>) -> anyhow::Result<Option<Credential>> {
+        if self.auth_method == MongoAuthMethod::None {
+            return Ok(None);
+        }
+        let username = Some(self.mongo_username.clone())
+            .filter(|username| self.auth_method.uses_username() && !username.is_empty());
+        let password = Some(self.mongo_password.clone())
+            .filter(|password| self.auth_method.uses_password() && !password.is_empty());
+        let mechanism = match self.auth_method.mechanism() {
+            Some(mechanism) => Some(AuthMechanism::from_str(mechanism)?),
+            None => None,
+        };
+        let mechanism_properties = match mechanism_properties.filter(|text| !text.is_empty()) {
+            Some(text) => Some(parse_mechanism_properties(text)?),
+            None => None,
+        };
+        Ok(Some(
+            Credential::builder()
+                .username(username)
+                .password(password)
+                .source(self.resolved_source())
+                .mechanism(mechanism)
+                .mechanism_properties(mechanism_properties)
+                .build(),
+        ))
+    }
+
+    /// 认证使用的数据库，`authSource` 为空时按认证方式取默认值
+    /// - 用户名密码（SCRAM）认证默认使用连接配置中的数据库，数据库为空时使用 `admin`
+    /// - X.509、AWS、OIDC 认证的用户保存在 `$external`
+    pub fn resolved_source(&self) -> Option<String> {
+        if let Some(auth_source) = non_empty(&self.options.auth_source) {
+            return Some(auth_source.to_string());
+        }
+        match self.auth_method {
+            MongoAuthMethod::None => None,
+            MongoAuthMethod::UserPass | MongoAuthMethod::ScramSha1 | MongoAuthMethod::ScramSha256 => {
+                Some(if self.db_name.is_empty() { "admin" } else { &self.db_name }.to_string())
+            }
+            MongoAuthMethod::X509 | MongoAuthMethod::Aws | MongoAuthMethod::Oidc => {
+                Some("$external".to_string())
+            }
+        }
+    }
+
+    /// 全部种子节点 `host:port`
+    pub fn members(&self) -> Vec<String> {
+        if self.options.hosts.is_empty() {
+            vec![format_host(&self.mongo_host, self.mongo_port)]
+        } else {
+            self.options.hosts.clone()
+        }
+    }
+}
+
+/// 解析 `mongodb://` 或 `mongodb+srv://` 连接串
+/// - 第一个节点作为 mongo_host / mongo_port，多个节点时全部保存在 `options.hosts`
+/// - 未指定数据库时使用 `test`
+pub fn parse_uri(uri: &str) -> anyhow::Result<ConnectionProfile> {
+    let uri = uri.trim();
+    let (srv, rest) = if let Some(rest) = uri.strip_prefix("mongodb+srv://") {
+        (true, rest)
+    } else if let Some(rest) = uri.strip_prefix("mongodb://") {
+        (false, rest)
+    } else {
+        bail!("connection string must start with mongodb:// or mongodb+srv://");
+    };
+
+    let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+    // 用户信息以最后一个 @ 为界，未编码的 @ 只会出现在密码中
+    let (userinfo, rest) = match rest.rfind('@') {
+        Some(i) => (Some(&rest[..i]), &rest[i + 1..]),
+        None => (None, rest),
+    };
+    let (host_list, db_name) = rest.split_once('/').unwrap_or((rest, ""));
+
+    let (mongo_username, mongo_password) = match userinfo {
+        Some(userinfo) => {
+            let (username, password) = userinfo.split_once(':').unwrap_or((userinfo, ""));
+            if username.is_empty() {
+                bail!("username must not be empty");
+            }
+            (percent_decode(username)?, percent_decode(password)?)
+        }
+        None => (String::new(), String::new()),
+    };
+
+    let mut hosts = Vec::new();
+    for host in host_list.split(',') {
+        hosts.push(parse_host(host)?);
+    }
+    if srv && (hosts.len() != 1 || host_list.contains(':')) {
+        bail!("mongodb+srv:// requires a single host name without a port");
+    }
+
+    let mut options = ConnectionOptions {
+        srv,
+        tls: srv,
+        ..Default::default()
+    };
+    if hosts.len() > 1 {
+        options.hosts = hosts
+            .iter()
+            .map(|(host, port)| format_host(host, *port))
+            .collect();
+    }
+    let mut auth_method = None;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid option '{}'", pair))?;
+        let (key, value) = (percent_decode(key)?, percent_decode(value)?);
+        match key.to_ascii_lowercase().as_str() {
+            "authmechanism" => auth_method = Some(MongoAuthMethod::from_mechanism(&value)?),
+            "replicaset" => options.replica_set = Some(value),
+            "authsource" => options.auth_source = Some(value),
+            "readpreference" => options.read_preference = Some(parse_read_preference(&value)?),
+            "directconnection" => options.direct_connection = Some(parse_bool(&key, &value)?),
+            "tls" | "ssl" => options.tls = parse_bool(&key, &value)?,
+            "tlscafile" => options.tls_ca_file = Some(value),
+            "tlsallowinvalidhostnames" => {
+                options.tls_allow_invalid_hostnames = parse_bool(&key, &value)?
+            }
+            _ => {
+                options.params.insert(key, value);
+            }
+        }
+    }
+
+    let (mongo_host, mongo_port) = hosts.swap_remove(0);
+    let db_name = percent_decode(db_name)?;
+    Ok(ConnectionProfile {
+        connect_type: "tp".to_string(),
+        auth_method: match auth_method {
+            Some(auth_method) => auth_method,
+            None if mongo_username.is_empty() => MongoAuthMethod::None,
+            None => MongoAuthMethod::UserPass,
+        },
+        mongo_host,
+        mongo_port,
+        mongo_username,
+        mongo_password,
+        db_name: if db_name.is_empty() {
+            "test".to_string()
+        } else {
+            db_name
+        },
+        options,
+        tls_certificate_key: String::new(),
+        tls_ca_certificate: String::new(),
+    })
+}
+
+/// 解析认证机制属性 `KEY:value,KEY:value`
+pub fn parse_mechanism_properties(text: &str) -> anyhow::Result<Document> {
+    let mut properties = Document::new();
+    for pair in text.split(',') {
+        let (key, value) = pair
+            .split_once(':')
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| anyhow!("invalid authMechanismProperties '{}'", text))?;
+        properties.insert(key, value);
+    }
+    Ok(properties)
+}
+
+/// 百分号编码，只保留 RFC 3986 中的非保留字符
+pub fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+/// 百分号解码
+pub fn percent_decode(text: &str) -> anyhow::Result<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text
+                .get(i + 1..i + 3)
+                .ok_or_else(|| anyhow!("invalid percent-encoding in '{}'", text))?;
+            let byte = u8::from_str_radix(hex, 16)
+                .map_err(|_| anyhow!("invalid percent-encoding in '{}'", text))?;
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| anyhow!("invalid UTF-8 in '{}'", text))
+}
+
+/// 解析 `host[:port]`，IPv6 地址需要写在方括号中
+pub fn parse_host(host: &str) -> anyhow::Result<(String, u16)> {
+    let (name, port) = if let Some(rest) = host.strip_prefix('[') {
+        let (name, rest) = rest
+            .split_once(']')
+            .ok_or_else(|| anyhow!("invalid host '{}'", host))?;
+        (name, rest.strip_prefix(':'))
+    } else {
+        match host.split_once(':') {
+            Some((name, port)) => (name, Some(port)),
+            None => (host, None),
+        }
+    };
+    if name.is_empty() {
+        bail!("host must not be empty");
+    }
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|_| anyhow!("invalid port in '{}'", host))?,
+        None => DEFAULT_PORT,
+    };
+    Ok((name.to_string(), port))
+}
+
+// IPv6 地址加上方括号
+fn format_host(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> anyhow::Result<bool> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => bail!("option {} must be true or false", key),
+    }
+}
+
+/// 检查读偏好名称，返回规范写法
+pub fn parse_read_preference(mode: &str) -> anyhow::Result<String> {
+    READ_PREFERENCES
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(mode))
+        .map(|name| name.to_string())
+        .ok_or_else(|| anyhow!("invalid readPreference '{}'", mode))
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|value| !value.is_empty())
+}
@@ -1,3 +1,6 @@
+use crate::utils::parser::ParseError;
+use serde::ser::SerializeStruct;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -13,13 +16,13 @@ pub enum Error {
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
     #[error(transparent)]
-    Json5(#[from] json5::Error),
-    #[error(transparent)]
     AnyHow(#[from] anyhow::Error),
     #[error(transparent)]
     Aes(#[from] aes_gcm::aes::cipher::InvalidLength),
     #[error(transparent)]
-    AsyncSsh2(#[from] async_ssh2_tokio::Error),
+    Parse(#[from] ParseError),
+    #[error("read-only connection: {0} is not allowed")]
+    ReadOnly(String),
 }
 
 impl serde::Serialize for Error {
@@ -27,6 +30,26 @@ impl serde::Serialize for Error {
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        match self {
+            // 解析错误以对象形式返回，方便前台定位出错位置
+            Error::Parse(e) => {
+                let mut state = serializer.serialize_struct("ParseError", 5)?;
+                state.serialize_field("kind", "parse")?;
+                state.serialize_field("line", &e.line)?;
+                state.serialize_field("column", &e.column)?;
+                state.serialize_field("reason", &e.reason)?;
+                state.serialize_field("message", &e.to_string())?;
+                state.end()
+            }
+            // 只读拦截单独标记，前台可据此提示而不是当作普通失败
+            Error::ReadOnly(operation) => {
+                let mut state = serializer.serialize_struct("ReadOnlyError", 3)?;
+                state.serialize_field("kind", "readOnly")?;
+                state.serialize_field("operation", operation)?;
+                state.serialize_field("message", &self.to_string())?;
+                state.end()
+            }
+            _ => serializer.serialize_str(self.to_string().as_ref()),
+        }
     }
 }
@@ -2,12 +2,17 @@ pub mod api;
 pub mod core;
 pub mod error;
 pub mod utils;
+#[cfg(test)]
 pub mod test;
 use api::api_mongo::{
-    mongo_clear_connection, mongo_collection, mongo_connect_server, mongo_data_encrypt,
-    mongo_delete_encrypt_data,
+    mongo_aggregate, mongo_clear_connection, mongo_close_cursor, mongo_collection,
+    mongo_connect_server, mongo_create_index, mongo_data_encrypt, mongo_delete_encrypt_data,
+    mongo_delete_many, mongo_delete_one, mongo_drop_index, mongo_explain, mongo_export_uri,
+    mongo_hide_index, mongo_insert_many, mongo_insert_one, mongo_list_databases,
+    mongo_list_indexes, mongo_next_batch, mongo_open_cursor, mongo_parse_uri, mongo_replace_one,
+    mongo_supports_invalid_hostnames, mongo_update_many, mongo_update_one,
 };
-use core::mongo::MongoConnections;
+use core::mongo::{sweep_cursors, MongoConnections};
 
 
 pub static MONGO_DATA_FILE: &str = "data.json";
@@ -28,9 +33,34 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         // 将连接池注册为全局状态
         .manage(MongoConnections::default())
+        // 后台定期关闭空闲的服务端游标
+        .setup(|app| {
+            tauri::async_runtime::spawn(sweep_cursors(app.handle().clone()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             mongo_connect_server,
+            mongo_list_databases,
             mongo_collection,
+            mongo_aggregate,
+            mongo_explain,
+            mongo_open_cursor,
+            mongo_next_batch,
+            mongo_close_cursor,
+            mongo_insert_one,
+            mongo_insert_many,
+            mongo_update_one,
+            mongo_update_many,
+            mongo_replace_one,
+            mongo_delete_one,
+            mongo_delete_many,
+            mongo_list_indexes,
+            mongo_create_index,
+            mongo_drop_index,
+            mongo_hide_index,
+            mongo_parse_uri,
+            mongo_export_uri,
+            mongo_supports_invalid_hostnames,
             mongo_clear_connection,
             mongo_data_encrypt,
             mongo_delete_encrypt_data
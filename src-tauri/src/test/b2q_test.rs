@@ -0,0 +1,65 @@
+use crate::core::mongo::{format_document, OutputFormat};
+use crate::utils::{b2q::*, q2b::*};
+use mongodb::bson::{
+	doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Decimal128, JavaScriptCodeWithScope,
+	Timestamp,
+};
+use serde_json::json;
+
+#[test]
+fn test_to_shell_string() {
+	assert_eq!(to_shell_string(&Bson::Int32(5)), "5");
+	assert_eq!(to_shell_string(&Bson::Int64(5)), "NumberLong(\"5\")");
+	assert_eq!(to_shell_string(&Bson::Double(5.0)), "5.0");
+	assert_eq!(to_shell_string(&Bson::Double(1e20)), "1e20");
+	assert_eq!(to_shell_string(&Bson::String("a\"b".to_string())), r#""a\"b""#);
+	assert_eq!(
+		to_shell_string(&Bson::DateTime(DateTime::from_millis(0))),
+		"ISODate(\"1970-01-01T00:00:00Z\")"
+	);
+	assert_eq!(
+		document_to_shell_string(&doc! { "a.b": 1, "$gt": [], "c": {} }),
+		r#"{ "a.b": 1, $gt: [], c: {} }"#
+	);
+}
+
+#[test]
+fn test_shell_string_round_trip() {
+	// 格式化后再解析，类型和值保持不变
+	let original = doc! {
+		"_id": ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap(),
+		"int": 1,
+		"long": 3000000000i64,
+		"small_long": Bson::Int64(1),
+		"double": 2.0,
+		"big_double": 1e17,
+		"decimal": "12.50".parse::<Decimal128>().unwrap(),
+		"date": DateTime::from_millis(1700000000123),
+		"ts": Timestamp { time: 1700000000, increment: 2 },
+		"uuid": Binary { subtype: BinarySubtype::Uuid, bytes: vec![7; 16] },
+		"bin": Binary { subtype: BinarySubtype::Generic, bytes: b"hello".to_vec() },
+		"re": bson_regex("^a.*", "mi"),
+		"re_slash": bson_regex("a/b", ""),
+		"code": Bson::JavaScriptCode("return 1".to_string()),
+		"scope": JavaScriptCodeWithScope { code: "x".to_string(), scope: doc! { "x": 1 } },
+		"keys": [Bson::MinKey, Bson::MaxKey, Bson::Null, Bson::Undefined, true],
+		"text": "line\nbreak \u{1F600}",
+		"nested": { "a.b": [{ "c": -1 }] },
+	};
+	let shell = document_to_shell_string(&original);
+	assert_eq!(query_to_document(&shell).unwrap(), original);
+}
+
+#[test]
+fn test_format_document() {
+	let doc = doc! { "n": 5i64, "d": 1.5 };
+	assert_eq!(
+		format_document(doc.clone(), OutputFormat::Canonical),
+		json!({ "n": { "$numberLong": "5" }, "d": { "$numberDouble": "1.5" } })
+	);
+	assert_eq!(format_document(doc.clone(), OutputFormat::Relaxed), json!({ "n": 5, "d": 1.5 }));
+	assert_eq!(
+		format_document(doc, OutputFormat::Shell),
+		json!("{ n: NumberLong(\"5\"), d: 1.5 }")
+	);
+}
@@ -0,0 +1,33 @@
+use crate::core::mongo::{cap_count, count_probe_limit, resolve_count_mode, CountMode};
+
+#[test]
+fn test_resolve_count_mode() {
+	assert_eq!(resolve_count_mode(CountMode::Estimated, true), CountMode::Estimated);
+	// 有条件时无法估算
+	assert_eq!(resolve_count_mode(CountMode::Estimated, false), CountMode::Capped);
+	assert_eq!(resolve_count_mode(CountMode::Exact, false), CountMode::Exact);
+	assert_eq!(resolve_count_mode(CountMode::Skip, true), CountMode::Skip);
+}
+
+#[test]
+fn test_cap_count() {
+	assert_eq!(cap_count(10001, CountMode::Capped, 10000), (10000, false));
+	assert_eq!(cap_count(10000, CountMode::Capped, 10000), (10000, true));
+	assert_eq!(cap_count(12, CountMode::Exact, 10000), (12, true));
+	assert_eq!(cap_count(12, CountMode::Estimated, 10000), (12, false));
+	assert_eq!(cap_count(0, CountMode::Skip, 10000), (0, false));
+}
+
+#[test]
+fn test_count_probe_limit() {
+	assert_eq!(count_probe_limit(10000), 10001);
+	assert_eq!(count_probe_limit(0), 1);
+	assert_eq!(count_probe_limit(i64::MAX as u64), i64::MAX);
+	assert_eq!(count_probe_limit(u64::MAX), i64::MAX);
+}
+
+#[test]
+fn test_count_mode_serde() {
+	assert_eq!(serde_json::to_value(CountMode::Capped).unwrap(), "capped");
+	assert_eq!(serde_json::from_str::<CountMode>("\"skip\"").unwrap(), CountMode::Skip);
+}
@@ -0,0 +1,58 @@
+use crate::core::mongo_explain::summarize_explain;
+use mongodb::bson::doc;
+
+#[test]
+fn test_summarize_index_scan() {
+	let explain = doc! {
+		"queryPlanner": {
+			"winningPlan": {
+				"stage": "LIMIT",
+				"inputStage": {
+					"stage": "FETCH",
+					"inputStage": { "stage": "IXSCAN", "indexName": "email_1", "keyPattern": { "email": 1 } },
+				},
+			},
+		},
+		"executionStats": {
+			"nReturned": 3,
+			"executionTimeMillis": 2,
+			"totalKeysExamined": 3,
+			"totalDocsExamined": 3i64,
+		},
+	};
+	let summary = summarize_explain(&explain);
+	assert_eq!(summary.stages, vec!["LIMIT", "FETCH", "IXSCAN"]);
+	assert_eq!(summary.indexes, vec!["email_1"]);
+	assert!(!summary.collection_scan);
+	assert_eq!(summary.docs_examined, Some(3));
+	assert_eq!(summary.keys_examined, Some(3));
+	assert_eq!(summary.docs_returned, Some(3));
+	assert_eq!(summary.execution_time_ms, Some(2));
+}
+
+#[test]
+fn test_summarize_sbe_and_sharded() {
+	// SBE 引擎把计划放在 queryPlan 下
+	let explain = doc! {
+		"queryPlanner": { "winningPlan": { "queryPlan": { "stage": "COLLSCAN" }, "slotBasedPlan": {} } },
+	};
+	let summary = summarize_explain(&explain);
+	assert_eq!(summary.stages, vec!["COLLSCAN"]);
+	assert!(summary.collection_scan);
+	assert_eq!(summary.docs_examined, None);
+
+	let explain = doc! {
+		"queryPlanner": {
+			"winningPlan": {
+				"stage": "SHARD_MERGE",
+				"shards": [
+					{ "shardName": "a", "winningPlan": { "stage": "IXSCAN", "indexName": "x_1" } },
+					{ "shardName": "b", "winningPlan": { "stage": "IXSCAN", "indexName": "x_1" } },
+				],
+			},
+		},
+	};
+	let summary = summarize_explain(&explain);
+	assert_eq!(summary.stages, vec!["SHARD_MERGE", "IXSCAN", "IXSCAN"]);
+	assert_eq!(summary.indexes, vec!["x_1"]);
+}
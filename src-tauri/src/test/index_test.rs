@@ -0,0 +1,27 @@
+use crate::core::mongo_index::parse_index_options;
+use mongodb::bson::doc;
+use std::time::Duration;
+
+#[test]
+fn test_parse_index_options() {
+	assert!(parse_index_options(None).unwrap().is_none());
+	assert!(parse_index_options(Some("  ")).unwrap().is_none());
+
+	let options = parse_index_options(Some(
+		"{ name: 'email_1', unique: true, expireAfterSeconds: 3600, partialFilterExpression: { active: true } }",
+	))
+	.unwrap()
+	.unwrap();
+	assert_eq!(options.name.as_deref(), Some("email_1"));
+	assert_eq!(options.unique, Some(true));
+	assert_eq!(options.expire_after, Some(Duration::from_secs(3600)));
+	assert_eq!(options.partial_filter_expression, Some(doc! { "active": true }));
+
+	let options = parse_index_options(Some("{ hidden: true, collation: { locale: 'fr' } }"))
+		.unwrap()
+		.unwrap();
+	assert_eq!(options.hidden, Some(true));
+	assert_eq!(options.collation.unwrap().locale, "fr");
+
+	assert!(parse_index_options(Some("{ unique: 'yes' }")).is_err());
+}
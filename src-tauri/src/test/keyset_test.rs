@@ -0,0 +1,109 @@
+use crate::core::keyset::*;
+use mongodb::bson::{doc, oid::ObjectId};
+
+#[test]
+fn test_keyset_sort() {
+	assert_eq!(keyset_sort(None), doc! { "_id": 1 });
+	assert_eq!(keyset_sort(Some(doc! { "age": -1 })), doc! { "age": -1, "_id": 1 });
+	assert_eq!(keyset_sort(Some(doc! { "_id": -1 })), doc! { "_id": -1 });
+}
+
+#[test]
+fn test_keyset_projection() {
+	let sort = doc! { "age": -1, "_id": 1 };
+	assert_eq!(keyset_projection(None, &sort), None);
+	// 包含式投影补上排序键
+	assert_eq!(
+		keyset_projection(Some(doc! { "name": 1 }), &sort),
+		Some(doc! { "name": 1, "age": 1, "_id": 1 })
+	);
+	// 排除式投影不能排除排序键
+	assert_eq!(
+		keyset_projection(Some(doc! { "age": 0, "big": 0 }), &sort),
+		Some(doc! { "big": 0 })
+	);
+}
+
+#[test]
+fn test_token_round_trip() {
+	let sort = doc! { "info.age": -1, "_id": 1 };
+	let id = ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+	let last = doc! { "_id": id, "info": { "age": 30 }, "name": "a" };
+	let token = encode_token(&sort, &last).unwrap();
+	assert_eq!(
+		decode_token(&token, &sort).unwrap(),
+		doc! { "$or": [
+			{ "$or": [{ "info.age": { "$lt": 30 } }, { "info.age": null }] },
+			{ "info.age": 30, "_id": { "$gt": id } },
+		] }
+	);
+	// 排序规则变化后令牌失效
+	assert!(decode_token(&token, &doc! { "_id": 1 }).is_err());
+	assert!(decode_token("not a token", &sort).is_err());
+}
+
+#[test]
+fn test_token_missing_sort_field() {
+	let id = ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+	// 最后一条文档缺少排序字段，记为 null
+	let last = doc! { "_id": id, "name": "a" };
+
+	// 升序时 null 排在最前，之后的文档为同为 null 的更大 _id 或任何不为 null 的值
+	let sort = doc! { "age": 1, "_id": 1 };
+	let token = encode_token(&sort, &last).unwrap();
+	assert_eq!(
+		decode_token(&token, &sort).unwrap(),
+		doc! { "$or": [
+			{ "age": { "$ne": null } },
+			{ "age": null, "_id": { "$gt": id } },
+		] }
+	);
+
+	// 降序时 null 排在最后，只剩同为 null 的更大 _id
+	let sort = doc! { "age": -1, "_id": 1 };
+	let token = encode_token(&sort, &last).unwrap();
+	assert_eq!(
+		decode_token(&token, &sort).unwrap(),
+		doc! { "$or": [{ "age": null, "_id": { "$gt": id } }] }
+	);
+
+	// 没有任何分支时不再返回文档
+	let sort = doc! { "age": -1 };
+	let token = encode_token(&sort, &last).unwrap();
+	assert_eq!(decode_token(&token, &sort).unwrap(), doc! { "$expr": false });
+}
+
+#[test]
+fn test_token_descending_to_missing() {
+	// 降序时数字排完后是缺少排序字段的文档，后续页必须包含它们
+	let sort = doc! { "age": -1, "_id": 1 };
+	let first = ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap();
+	let second = ObjectId::parse_str("507f1f77bcf86cd799439012").unwrap();
+
+	let token = encode_token(&sort, &doc! { "_id": first, "age": 1 }).unwrap();
+	let after = decode_token(&token, &sort).unwrap();
+	assert_eq!(
+		after,
+		doc! { "$or": [
+			{ "$or": [{ "age": { "$lt": 1 } }, { "age": null }] },
+			{ "age": 1, "_id": { "$gt": first } },
+		] }
+	);
+
+	// 翻到缺少字段的文档之后，只剩同样缺少字段的更大 _id
+	let token = encode_token(&sort, &doc! { "_id": second }).unwrap();
+	assert_eq!(
+		decode_token(&token, &sort).unwrap(),
+		doc! { "$or": [{ "age": null, "_id": { "$gt": second } }] }
+	);
+}
+
+#[test]
+fn test_merge_filter() {
+	let after = doc! { "$or": [{ "_id": { "$gt": 1 } }] };
+	assert_eq!(merge_filter(doc! {}, after.clone()), after);
+	assert_eq!(
+		merge_filter(doc! { "a": 1 }, after.clone()),
+		doc! { "$and": [{ "a": 1 }, after] }
+	);
+}
@@ -1 +1,13 @@
-pub mod q2b_test;
\ No newline at end of file
+pub mod b2q_test;
+pub mod count_test;
+pub mod explain_test;
+pub mod index_test;
+pub mod keyset_test;
+pub mod parser_test;
+pub mod q2b_test;
+pub mod read_only_test;
+pub mod socks_test;
+pub mod ssh_test;
+pub mod stats_test;
+pub mod tls_test;
+pub mod uri_test;
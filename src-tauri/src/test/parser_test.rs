@@ -0,0 +1,134 @@
+use crate::utils::parser::*;
+use mongodb::bson::{doc, oid::ObjectId, Bson};
+
+#[test]
+fn test_parse_relaxed_syntax() {
+	// 不加引号的键、单引号、注释、尾随逗号、点路径
+	let query = r#"
+		{
+			// 行注释
+			name: 'leleo',
+			"age": { $gte: 18, $lt: 60, },
+			/* 块注释 */
+			tags.0: "a",
+			list: [1, 2.5, -3, 0x10, true, null,],
+		}
+	"#;
+	assert_eq!(
+		parse_document(query).unwrap(),
+		doc! {
+			"name": "leleo",
+			"age": { "$gte": 18, "$lt": 60 },
+			"tags.0": "a",
+			"list": [1, 2.5, -3, 16, true, Bson::Null],
+		}
+	);
+}
+
+#[test]
+fn test_parse_numbers() {
+	let doc = parse_document("{ a: 1, b: 3000000000, c: 1.5e3, d: -Infinity, e: .5 }").unwrap();
+	assert_eq!(doc.get("a"), Some(&Bson::Int32(1)));
+	assert_eq!(doc.get("b"), Some(&Bson::Int64(3000000000)));
+	assert_eq!(doc.get("c"), Some(&Bson::Double(1500.0)));
+	assert_eq!(doc.get("d"), Some(&Bson::Double(f64::NEG_INFINITY)));
+	assert_eq!(doc.get("e"), Some(&Bson::Double(0.5)));
+}
+
+#[test]
+fn test_string_data_is_not_a_constructor() {
+	// 字符串中的内容保持原样，不会被识别为类型构造器
+	let doc = parse_document(r#"{ a: 'ObjectId("507f1f77bcf86cd799439011")', b: "ISODate(2025-08-28)", c: "/x/i" }"#).unwrap();
+	assert_eq!(doc.get_str("a").unwrap(), r#"ObjectId("507f1f77bcf86cd799439011")"#);
+	assert_eq!(doc.get_str("b").unwrap(), "ISODate(2025-08-28)");
+	assert_eq!(doc.get_str("c").unwrap(), "/x/i");
+}
+
+#[test]
+fn test_parse_string_escapes() {
+	let doc = parse_document(r#"{ a: "say \"hi\"", b: 'it\'s', c: "中\x41\n" }"#).unwrap();
+	assert_eq!(doc.get_str("a").unwrap(), r#"say "hi""#);
+	assert_eq!(doc.get_str("b").unwrap(), "it's");
+	assert_eq!(doc.get_str("c").unwrap(), "中A\n");
+
+	// 代理对
+	let doc = parse_document(r#"{ a: "\uD83D\uDE00" }"#).unwrap();
+	assert_eq!(doc.get_str("a").unwrap(), "😀");
+	// 单独的代理项或不成对的代理对
+	for input in [r#"{ a: "\uD83D" }"#, r#"{ a: "\uD83Dx" }"#, r#"{ a: "\uD83D\u0041" }"#, r#"{ a: "\uDE00" }"#] {
+		assert!(parse_document(input).is_err(), "{}", input);
+	}
+}
+
+#[test]
+fn test_parse_depth_limit() {
+	let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+	assert!(parse_value(&nested(100)).is_ok());
+	let err = parse_value(&nested(101)).unwrap_err();
+	assert!(err.reason.contains("maximum depth"));
+	// 极深的输入返回错误而不是栈溢出
+	assert!(parse_value(&nested(100_000)).is_err());
+	assert!(parse_document(&format!("{}1{}", "{ a: ".repeat(100_000), " }".repeat(100_000))).is_err());
+}
+
+#[test]
+fn test_parse_nested_constructors() {
+	let doc = parse_document(r#"{ _id: { $in: [ObjectId( '507f1f77bcf86cd799439011' ), new ObjectId("507f1f77bcf86cd799439012")] } }"#).unwrap();
+	assert_eq!(
+		doc,
+		doc! { "_id": { "$in": [
+			ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap(),
+			ObjectId::parse_str("507f1f77bcf86cd799439012").unwrap(),
+		] } }
+	);
+}
+
+#[test]
+fn test_parse_value() {
+	assert_eq!(
+		parse_value("[{ $match: { a: 1 } }, { $limit: 5 }]").unwrap(),
+		Bson::Array(vec![
+			Bson::Document(doc! { "$match": { "a": 1 } }),
+			Bson::Document(doc! { "$limit": 5 }),
+		])
+	);
+}
+
+#[test]
+fn test_parse_errors() {
+	// 报告错误的行列号
+	let err = parse_document("{\n  a: 1,\n  b: ,\n}").unwrap_err();
+	assert_eq!((err.line, err.column), (3, 6));
+
+	let err = parse_document("[1, 2]").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 1));
+	assert!(err.reason.contains("expected a document"));
+
+	let err = parse_document("{ a: ObjectId(\"xyz\") }").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 6));
+	assert!(err.reason.starts_with("ObjectId"));
+
+	let err = parse_document("{ a: foo }").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 6));
+
+	let err = parse_document("{ a: 'abc }").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 6));
+
+	let err = parse_document("{ a: 1 } x").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 10));
+
+	let err = parse_document("{ a: 1").unwrap_err();
+	assert!(err.reason.contains("unterminated document"));
+	assert_eq!(err.to_string(), format!("line 1, column 7: {}", err.reason));
+}
+
+#[test]
+fn test_parse_error_serialize() {
+	// 解析错误以带行列号的对象返回给前台
+	let err = crate::error::Error::from(parse_document("{ a: [1, 2 }").unwrap_err());
+	let value = serde_json::to_value(&err).unwrap();
+	assert_eq!(value["kind"], "parse");
+	assert_eq!(value["line"], 1);
+	assert_eq!(value["column"], 12);
+	assert_eq!(value["message"], err.to_string());
+}
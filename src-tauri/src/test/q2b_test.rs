@@ -1,91 +1,288 @@
+use crate::core::mongo::{CollectionParams, CountMode, QueryOptions};
 use crate::utils::q2b::*;
-use mongodb::bson::{doc, Bson, DateTime, Decimal128, Document,to_document, oid::ObjectId};
-use regex::Regex;
-use serde_json::Value;
+use mongodb::bson::{
+	doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Decimal128, Document,
+	Timestamp,
+};
+use mongodb::options::{Hint, UpdateModifications};
 
 #[test]
-fn test_parse_special_wrapper() {
-	// 测试 ISODate 格式
+fn test_empty_query() {
+	// 空语句匹配全部
+	assert_eq!(query_to_document("").unwrap(), doc! {});
+	assert_eq!(query_to_document("  \n ").unwrap(), doc! {});
+}
+
+#[test]
+fn test_construct() {
+	assert!(matches!(
+		construct("ISODate", vec![Bson::String("2025-08-28T00:00:00Z".to_string())]),
+		Ok(Bson::DateTime(_))
+	));
+	assert!(matches!(
+		construct("ObjectId", vec![Bson::String("507f1f77bcf86cd799439011".to_string())]),
+		Ok(Bson::ObjectId(_))
+	));
+	assert!(matches!(
+		construct("NumberDecimal", vec![Bson::String("123.456".to_string())]),
+		Ok(Bson::Decimal128(_))
+	));
+	// 无效参数和未知构造器
+	assert!(construct("ObjectId", vec![Bson::String("xyz".to_string())]).is_err());
+	assert!(construct("Invalid", vec![Bson::Int32(123)]).is_err());
+	assert!(construct("NumberInt", vec![Bson::Int32(1), Bson::Int32(2)]).is_err());
+}
+
+#[test]
+fn test_convert_document() {
+	// 测试嵌套文档转换
+	let query = r#"
+		{
+		date: ISODate("2025-08-28T00:00:00Z"),
+		oid: ObjectId("507f1f77bcf86cd799439011"),
+		decimal: NumberDecimal("123.456"),
+		nested: {
+			date: ISODate("2025-08-28T00:00:00Z")
+			}
+		}
+	"#;
+	let converted = query_to_document(query).unwrap();
+	assert!(matches!(converted.get("date"), Some(Bson::DateTime(_))));
+	assert!(matches!(converted.get("oid"), Some(Bson::ObjectId(_))));
+	assert_eq!(
+		converted.get("decimal"),
+		Some(&Bson::Decimal128("123.456".parse::<Decimal128>().unwrap()))
+	);
+	assert!(matches!(
+		converted.get_document("nested").unwrap().get("date"),
+		Some(Bson::DateTime(_))
+	));
+}
+
+// 按 mongo_collection 的流程解析查询语句
+fn parse_query(query: &str) -> Document {
+	query_to_document(query).unwrap()
+}
+
+#[test]
+fn test_number_literals() {
+	let converted = parse_query(r#"{ a: NumberLong(123), b: NumberLong("9007199254740993"), c: NumberInt(7), d: NumberInt("8") }"#);
+	assert_eq!(converted.get("a"), Some(&Bson::Int64(123)));
+	assert_eq!(converted.get("b"), Some(&Bson::Int64(9007199254740993)));
+	assert_eq!(converted.get("c"), Some(&Bson::Int32(7)));
+	assert_eq!(converted.get("d"), Some(&Bson::Int32(8)));
+}
+
+#[test]
+fn test_date_literals() {
+	let converted = parse_query(r#"{
+		a: new Date("2025-08-28T00:00:00Z"),
+		b: ISODate("2025-08-28"),
+		c: new Date(1700000000000),
+		d: ISODate("2025-08-28T08:30:00"),
+		e: new Date()
+	}"#);
+	let expected = DateTime::parse_rfc3339_str("2025-08-28T00:00:00Z").unwrap();
+	assert_eq!(converted.get("a"), Some(&Bson::DateTime(expected)));
+	assert_eq!(converted.get("b"), Some(&Bson::DateTime(expected)));
+	assert_eq!(converted.get("c"), Some(&Bson::DateTime(DateTime::from_millis(1700000000000))));
+	assert_eq!(
+		converted.get("d"),
+		Some(&Bson::DateTime(DateTime::parse_rfc3339_str("2025-08-28T08:30:00Z").unwrap()))
+	);
+	assert!(matches!(converted.get("e"), Some(Bson::DateTime(_))));
+}
+
+#[test]
+fn test_binary_literals() {
+	let converted = parse_query(r#"{
+		a: UUID("0e3b9d3a-8f4c-4a8e-9b0a-1f2e3d4c5b6a"),
+		b: BinData(0, "aGVsbG8="),
+		c: HexData(5, "68656c6c6f")
+	}"#);
+	match converted.get("a") {
+		Some(Bson::Binary(bin)) => {
+			assert_eq!(bin.subtype, BinarySubtype::Uuid);
+			assert_eq!(bin.bytes.len(), 16);
+		}
+		other => panic!("unexpected {:?}", other),
+	}
+	assert_eq!(
+		converted.get("b"),
+		Some(&Bson::Binary(Binary { subtype: BinarySubtype::Generic, bytes: b"hello".to_vec() }))
+	);
 	assert_eq!(
-		parse_special_wrapper("ISODate(\"2025-08-28T00:00:00Z\")", "ISODate"),
-		Some("\"2025-08-28T00:00:00Z\"")
+		converted.get("c"),
+		Some(&Bson::Binary(Binary { subtype: BinarySubtype::Md5, bytes: b"hello".to_vec() }))
 	);
+}
 
-	// 测试 ObjectId 格式
+#[test]
+fn test_timestamp_literal() {
+	let converted = parse_query(r#"{ ts: Timestamp(1700000000, 1) }"#);
 	assert_eq!(
-		parse_special_wrapper("ObjectId(\"507f1f77bcf86cd799439011\")", "ObjectId"),
-		Some("\"507f1f77bcf86cd799439011\"")
+		converted.get("ts"),
+		Some(&Bson::Timestamp(Timestamp { time: 1700000000, increment: 1 }))
 	);
+}
 
-	// 测试 NumberDecimal 格式
+#[test]
+fn test_regex_literals() {
+	let converted = parse_query(r#"{ a: /^ab\d+,c/i, b: { $in: [/x\/y/, RegExp("a,b")] }, c: new RegExp("z", "ms") }"#);
+	assert_eq!(
+		converted.get("a"),
+		Some(&Bson::RegularExpression(bson_regex(r"^ab\d+,c", "i")))
+	);
+	assert_eq!(
+		converted.get_document("b").unwrap().get_array("$in").unwrap(),
+		&vec![
+			Bson::RegularExpression(bson_regex(r"x\/y", "")),
+			Bson::RegularExpression(bson_regex("a,b", "")),
+		]
+	);
 	assert_eq!(
-		parse_special_wrapper("NumberDecimal(\"123.456\")", "NumberDecimal"),
-		Some("\"123.456\"")
+		converted.get("c"),
+		Some(&Bson::RegularExpression(bson_regex("z", "ms")))
 	);
+}
+
+#[test]
+fn test_key_literals() {
+	let converted = parse_query(r#"{ a: MinKey, b: MaxKey(), c: [MinKey()] }"#);
+	assert_eq!(converted.get("a"), Some(&Bson::MinKey));
+	assert_eq!(converted.get("b"), Some(&Bson::MaxKey));
+	assert_eq!(converted.get_array("c").unwrap(), &vec![Bson::MinKey]);
+}
 
-	// 测试无效格式
-	assert_eq!(parse_special_wrapper("Invalid(\"123\")", "ISODate"), None);
+#[test]
+fn test_dbref_literal() {
+	let converted = parse_query(r#"{ owner: DBRef("users", ObjectId("507f1f77bcf86cd799439011"), "app") }"#);
+	assert_eq!(
+		converted.get_document("owner").unwrap(),
+		&doc! {
+			"$ref": "users",
+			"$id": ObjectId::parse_str("507f1f77bcf86cd799439011").unwrap(),
+			"$db": "app",
+		}
+	);
 }
 
 #[test]
-fn test_preprocess_query() {
-	// 测试正则预处理
-	let query = "ISODate(\"2025-08-28T00:00:00Z\")";
-	let processed = preprocess_query(query);
+fn test_code_literal() {
+	let converted = parse_query(r#"{ fn: Code("function() { return 1 }") }"#);
 	assert_eq!(
-		processed,
-		"\"ISODate(2025-08-28T00:00:00Z)\""
+		converted.get("fn"),
+		Some(&Bson::JavaScriptCode("function() { return 1 }".to_string()))
 	);
 }
 
 #[test]
-fn test_convert_value() {
-	// 测试 ISODate 转换
-	let date_str = "ISODate(2025-08-28T00:00:00Z)";
-	let bson_str = Bson::String(date_str.to_string());
-	let converted = convert_value(bson_str);
-	assert!(matches!(converted, Bson::DateTime(_)));
+fn test_optional_document() {
+	assert_eq!(optional_document(None).unwrap(), None);
+	assert_eq!(optional_document(Some(" ")).unwrap(), None);
+	assert_eq!(optional_document(Some("{ name: 1, _id: 0 }")).unwrap(), Some(doc! { "name": 1, "_id": 0 }));
+	assert!(optional_document(Some("[1]")).is_err());
+}
 
-	// 测试 ObjectId 转换
-	let oid_str = "ObjectId(507f1f77bcf86cd799439011)";
-	let bson_str = Bson::String(oid_str.to_string());
-	let converted = convert_value(bson_str);
-	assert!(matches!(converted, Bson::ObjectId(_)));
+#[test]
+fn test_parse_hint() {
+	assert!(matches!(parse_hint(Some("")), Ok(None)));
+	assert!(matches!(parse_hint(Some("name_1")), Ok(Some(Hint::Name(name))) if name == "name_1"));
+	assert!(matches!(parse_hint(Some("'name_1'")), Ok(Some(Hint::Name(name))) if name == "name_1"));
+	assert!(matches!(parse_hint(Some("{ name: 1 }")), Ok(Some(Hint::Keys(keys))) if keys == doc! { "name": 1 }));
+	assert!(parse_hint(Some("{ name: }")).is_err());
+}
 
-	// 测试 NumberDecimal 转换
-	let nd_str = "NumberDecimal(123.456)";
-	let bson_str = Bson::String(nd_str.to_string());
-	let converted = convert_value(bson_str);
-	assert!(matches!(converted, Bson::Decimal128(_)));
+#[test]
+fn test_query_options() {
+	let options = QueryOptions::new(
+		Some("{ name: 1 }".to_string()),
+		Some("{ created: -1 }".to_string()),
+		Some("{ locale: 'zh', strength: 2 }".to_string()),
+		None,
+		Some(5000),
+	)
+	.parse()
+	.unwrap();
+	assert_eq!(options.projection, Some(doc! { "name": 1 }));
+	assert_eq!(options.sort, Some(doc! { "created": -1 }));
+	assert_eq!(options.collation.unwrap().locale, "zh");
+	assert!(options.hint.is_none());
+	assert_eq!(options.max_time, Some(std::time::Duration::from_millis(5000)));
 
-	// 测试无效字符串
-	let invalid_str = "Invalid(123)";
-	let bson_str = Bson::String(invalid_str.to_string());
-	let converted = convert_value(bson_str);
-	assert!(matches!(converted, Bson::String(_)));
+	// 排序规则缺少 locale
+	let options = QueryOptions::new(None, None, Some("{ strength: 2 }".to_string()), None, None);
+	assert!(options.parse().is_err());
 }
 
 #[test]
-fn test_convert_document() {
-	// 测试嵌套文档转换
-	let query = r#"
-		{
-		date: ISODate("2025-08-28T00:00:00Z"),
-		oid: ObjectId("507f1f77bcf86cd799439011"),
-		decimal: NumberDecimal("123.456"),
-		nested: {
-			date: ISODate("2025-08-28T00:00:00Z")
-			}
-		}
-	"#;
-	let json: Value = json5::from_str(&preprocess_query(query)).unwrap();
-	let doc = to_document(&json).unwrap();
-	let converted = convert_document(doc);
-	assert!(matches!(converted.get("date"), Some(Bson::DateTime(_))));
-	assert!(matches!(converted.get("oid"), Some(Bson::ObjectId(_))));
-	assert!(matches!(converted.get("decimal"), Some(Bson::Decimal128(_))));
+fn test_collection_params() {
+	// 前台传入的参数为驼峰命名，查询选项与其他参数平铺在一起
+	let params: CollectionParams = serde_json::from_value(serde_json::json!({
+		"collectionName": "users",
+		"page": 2,
+		"pageSize": 50,
+		"query": "{ age: { $gt: 18 } }",
+		"sort": "{ created: -1 }",
+		"maxTimeMs": 5000,
+		"countMode": "capped",
+	}))
+	.unwrap();
+	assert_eq!(params.collection_name, "users");
+	assert_eq!((params.page, params.page_size, params.keyset), (Some(2), Some(50), None));
+	assert_eq!(params.count_mode, Some(CountMode::Capped));
+	let options = params.options.parse().unwrap();
+	assert_eq!(options.sort, Some(doc! { "created": -1 }));
+	assert_eq!(options.max_time, Some(std::time::Duration::from_millis(5000)));
+	assert!(options.projection.is_none());
+}
+
+#[test]
+fn test_parse_pipeline() {
+	assert!(parse_pipeline("").unwrap().is_empty());
+	assert_eq!(
+		parse_pipeline("[{ $match: { a: NumberLong(1) } }, { $group: { _id: '$b', n: { $sum: 1 } } }]").unwrap(),
+		vec![
+			doc! { "$match": { "a": 1i64 } },
+			doc! { "$group": { "_id": "$b", "n": { "$sum": 1 } } },
+		]
+	);
+	// 单个阶段
+	assert_eq!(parse_pipeline("{ $limit: 1 }").unwrap(), vec![doc! { "$limit": 1 }]);
+	assert!(parse_pipeline("[{ $limit: 1 }, 2]").is_err());
+	let err = parse_pipeline("  'abc'").unwrap_err();
+	assert_eq!((err.line, err.column), (1, 3));
+	// 出错位置指向该阶段本身，而不是嵌套数组中的元素
+	let err = parse_pipeline("[\n  { $match: { a: [1, 2] } },\n  { $limit: 1 },\n  [{ $skip: 1 }]\n]").unwrap_err();
+	assert_eq!(err.reason, "pipeline stage 2 is not a document");
+	assert_eq!((err.line, err.column), (4, 3));
+}
+
+#[test]
+fn test_parse_documents() {
+	assert_eq!(
+		parse_documents("[{ a: 1 }, { b: NumberLong(2) }]").unwrap(),
+		vec![doc! { "a": 1 }, doc! { "b": 2i64 }]
+	);
+	assert_eq!(parse_documents("{ a: 1 }").unwrap(), vec![doc! { "a": 1 }]);
+	let err = parse_documents("[{ a: 1 }, 'x']").unwrap_err();
+	assert_eq!(err.reason, "element 1 is not a document");
+	assert_eq!((err.line, err.column), (1, 12));
+}
+
+#[test]
+fn test_parse_update() {
 	assert!(matches!(
-		converted.get("nested").and_then(|v| v.as_document()),
-		Some(_)
+		parse_update("{ $set: { a: 1 } }").unwrap(),
+		UpdateModifications::Document(update) if update == doc! { "$set": { "a": 1 } }
 	));
-}
\ No newline at end of file
+	assert!(matches!(
+		parse_update("[{ $set: { a: '$b' } }]").unwrap(),
+		UpdateModifications::Pipeline(stages) if stages == vec![doc! { "$set": { "a": "$b" } }]
+	));
+	let err = parse_update("\n 1").unwrap_err();
+	assert_eq!((err.line, err.column), (2, 2));
+	let err = parse_update("[{ $set: { a: 1 } }, 'x']").unwrap_err();
+	assert_eq!(err.reason, "pipeline stage 1 is not a document");
+	assert_eq!((err.line, err.column), (1, 22));
+}
@@ -0,0 +1,82 @@
+use crate::core::mongo::MongoData;
+use crate::error::Error;
+use mongodb::bson::doc;
+use serde_json::json;
+
+// 与前台保存的连接配置相同的结构，额外字段被忽略
+fn mongo_data(read_only: bool) -> MongoData {
+	serde_json::from_value(json!({
+		"id": "id",
+		"type": "tp",
+		"authMethod": "none",
+		"sshHost": "",
+		"sshPort": 22,
+		"sshUsername": "",
+		"sshAuthMethod": "password",
+		"mongoHost": "127.0.0.1",
+		"mongoPort": 27017,
+		"mongoUsername": "",
+		"dbName": "test",
+		"readOnly": read_only,
+	}))
+	.unwrap()
+}
+
+#[test]
+fn test_ensure_writable() {
+	assert!(mongo_data(false).ensure_writable("insertOne").is_ok());
+	let err = mongo_data(true).ensure_writable("insertOne").unwrap_err();
+	assert!(matches!(err, Error::ReadOnly(ref op) if op == "insertOne"));
+	assert_eq!(err.to_string(), "read-only connection: insertOne is not allowed");
+}
+
+#[test]
+fn test_saved_profile_read_only() {
+	// 调用方省略 readOnly，以保存的连接配置为准
+	let mut omitted: MongoData = serde_json::from_value(json!({
+		"id": "id",
+		"type": "tp",
+		"authMethod": "none",
+		"sshHost": "",
+		"sshPort": 22,
+		"sshUsername": "",
+		"sshAuthMethod": "password",
+		"mongoHost": "127.0.0.1",
+		"mongoPort": 27017,
+		"mongoUsername": "",
+		"dbName": "test",
+	}))
+	.unwrap();
+	assert!(omitted.ensure_writable("insertOne").is_ok());
+	omitted.apply_saved_profile(&json!({ "id": "id", "readOnly": true }));
+	let err = omitted.ensure_writable("insertOne").unwrap_err();
+	assert!(matches!(err, Error::ReadOnly(ref op) if op == "insertOne"));
+
+	// 调用方传入 false 也不能覆盖保存的只读设置
+	let mut writable = mongo_data(false);
+	writable.apply_saved_profile(&json!({ "id": "id", "readOnly": true }));
+	assert!(writable.ensure_writable("deleteMany").is_err());
+	writable.apply_saved_profile(&json!({ "id": "id" }));
+	assert!(writable.ensure_writable("deleteMany").is_ok());
+}
+
+#[test]
+fn test_ensure_pipeline_allowed() {
+	let read_only = mongo_data(true);
+	assert!(read_only.ensure_pipeline_allowed(&[doc! { "$match": { "a": 1 } }]).is_ok());
+	assert!(read_only.ensure_pipeline_allowed(&[doc! { "$match": {} }, doc! { "$out": "copy" }]).is_err());
+	assert!(read_only.ensure_pipeline_allowed(&[doc! { "$merge": { "into": "copy" } }]).is_err());
+	assert!(mongo_data(false).ensure_pipeline_allowed(&[doc! { "$out": "copy" }]).is_ok());
+}
+
+#[test]
+fn test_ensure_command_allowed() {
+	let read_only = mongo_data(true);
+	assert!(read_only.ensure_command_allowed(&doc! { "collStats": "users", "scale": 1 }).is_ok());
+	assert!(read_only.ensure_command_allowed(&doc! { "dropDatabase": 1 }).is_err());
+	assert!(read_only.ensure_command_allowed(&doc! { "createIndexes": "users", "indexes": [] }).is_err());
+	assert!(read_only
+		.ensure_command_allowed(&doc! { "aggregate": "users", "pipeline": [{ "$out": "copy" }], "cursor": {} })
+		.is_err());
+	assert!(mongo_data(false).ensure_command_allowed(&doc! { "dropDatabase": 1 }).is_ok());
+}
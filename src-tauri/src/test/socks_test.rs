@@ -0,0 +1,99 @@
+use crate::core::socks::{accept_request, reply, Reply};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+// 用户名密码认证请求
+fn auth_request(username: &str, password: &str) -> Vec<u8> {
+	let mut bytes = vec![0x01, username.len() as u8];
+	bytes.extend_from_slice(username.as_bytes());
+	bytes.push(password.len() as u8);
+	bytes.extend_from_slice(password.as_bytes());
+	bytes
+}
+
+#[tokio::test]
+async fn test_socks_connect_domain() {
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move {
+		let target = accept_request(&mut server, "user", "pass").await.unwrap();
+		reply(&mut server, Reply::Succeeded).await.unwrap();
+		target
+	});
+
+	client.write_all(&[0x05, 0x02, 0x00, 0x02]).await.unwrap();
+	let mut method = [0u8; 2];
+	client.read_exact(&mut method).await.unwrap();
+	assert_eq!(method, [0x05, 0x02]);
+
+	client.write_all(&auth_request("user", "pass")).await.unwrap();
+	let mut status = [0u8; 2];
+	client.read_exact(&mut status).await.unwrap();
+	assert_eq!(status, [0x01, 0x00]);
+
+	// 主机名原样交给服务端，由跳板机解析
+	let host = b"db2.internal";
+	let mut request = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+	request.extend_from_slice(host);
+	request.extend_from_slice(&27018u16.to_be_bytes());
+	client.write_all(&request).await.unwrap();
+	let mut response = [0u8; 10];
+	client.read_exact(&mut response).await.unwrap();
+	assert_eq!(response[..2], [0x05, 0x00]);
+
+	assert_eq!(server.await.unwrap(), ("db2.internal".to_string(), 27018));
+}
+
+#[tokio::test]
+async fn test_socks_connect_ip() {
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move { accept_request(&mut server, "u", "p").await.unwrap() });
+
+	client.write_all(&[0x05, 0x01, 0x02]).await.unwrap();
+	client.write_all(&auth_request("u", "p")).await.unwrap();
+	client.write_all(&[0x05, 0x01, 0x00, 0x01, 10, 0, 0, 5, 0x69, 0x89]).await.unwrap();
+	assert_eq!(server.await.unwrap(), ("10.0.0.5".to_string(), 27017));
+
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move { accept_request(&mut server, "u", "p").await.unwrap() });
+	client.write_all(&[0x05, 0x01, 0x02]).await.unwrap();
+	client.write_all(&auth_request("u", "p")).await.unwrap();
+	let mut request = vec![0x05, 0x01, 0x00, 0x04];
+	request.extend_from_slice(&"::1".parse::<std::net::Ipv6Addr>().unwrap().octets());
+	request.extend_from_slice(&27017u16.to_be_bytes());
+	client.write_all(&request).await.unwrap();
+	assert_eq!(server.await.unwrap(), ("::1".to_string(), 27017));
+}
+
+#[tokio::test]
+async fn test_socks_rejects_without_password() {
+	// 不提供用户名密码认证的客户端被拒绝
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move { accept_request(&mut server, "u", "p").await });
+	client.write_all(&[0x05, 0x01, 0x00]).await.unwrap();
+	let mut method = [0u8; 2];
+	client.read_exact(&mut method).await.unwrap();
+	assert_eq!(method, [0x05, 0xFF]);
+	assert!(server.await.unwrap().is_err());
+
+	// 用户名或密码错误
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move { accept_request(&mut server, "u", "p").await });
+	client.write_all(&[0x05, 0x01, 0x02]).await.unwrap();
+	client.write_all(&auth_request("u", "wrong")).await.unwrap();
+	let mut response = [0u8; 4];
+	client.read_exact(&mut response).await.unwrap();
+	assert_eq!(response, [0x05, 0x02, 0x01, 0x01]);
+	assert!(server.await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn test_socks_rejects_bind_command() {
+	let (mut client, mut server) = duplex(1024);
+	let server = tokio::spawn(async move { accept_request(&mut server, "u", "p").await });
+	client.write_all(&[0x05, 0x01, 0x02]).await.unwrap();
+	client.write_all(&auth_request("u", "p")).await.unwrap();
+	client.write_all(&[0x05, 0x02, 0x00, 0x01, 127, 0, 0, 1, 0, 80]).await.unwrap();
+	let mut response = [0u8; 14];
+	client.read_exact(&mut response).await.unwrap();
+	assert_eq!(response[4..6], [0x05, 0x07]);
+	assert!(server.await.unwrap().is_err());
+}
@@ -0,0 +1,65 @@
+use crate::core::mongo::{as_number, extract_coll_stats};
+use mongodb::bson::{doc, Bson};
+
+#[test]
+fn test_as_number() {
+	assert_eq!(as_number(&Bson::Int32(7)), Some(7));
+	assert_eq!(as_number(&Bson::Int64(5_000_000_000)), Some(5_000_000_000));
+	assert_eq!(as_number(&Bson::Double(1.5e10)), Some(15_000_000_000));
+	assert_eq!(as_number(&Bson::Double(f64::NAN)), None);
+	assert_eq!(as_number(&Bson::String("1".into())), None);
+}
+
+#[test]
+fn test_extract_coll_stats() {
+	// 大集合的数值可能是 Int64 或 Double
+	let stats = doc! {
+		"size": 6_000_000_000i64,
+		"count": 3_000_000i64,
+		"avgObjSize": 2000.0,
+		"storageSize": 2.5e9,
+		"totalIndexSize": 120_000_000,
+		"nindexes": 2,
+		"indexSizes": { "_id_": 100_000_000, "email_1": 20_000_000i64 },
+		"capped": false,
+	};
+	let coll_stats = extract_coll_stats("users", &[stats]);
+	assert_eq!(
+		coll_stats,
+		doc! {
+			"collection": "users",
+			"size": 6_000_000_000i64,
+			"count": 3_000_000i64,
+			"avgObjSize": 2000i64,
+			"storageSize": 2_500_000_000i64,
+			"totalIndexSize": 120_000_000i64,
+			"indexCount": 2i64,
+			"indexes": ["_id_", "email_1"],
+			"indexSizes": { "_id_": 100_000_000i64, "email_1": 20_000_000i64 },
+			"capped": false,
+		}
+	);
+}
+
+#[test]
+fn test_extract_sharded_coll_stats() {
+	let shard = doc! {
+		"size": 100,
+		"count": 10,
+		"storageSize": 4096,
+		"nindexes": 1,
+		"indexSizes": { "_id_": 4096 },
+		"capped": true,
+	};
+	let coll_stats = extract_coll_stats("logs", &[shard.clone(), shard]);
+	assert_eq!(coll_stats.get_i64("size").unwrap(), 200);
+	assert_eq!(coll_stats.get_i64("count").unwrap(), 20);
+	assert_eq!(coll_stats.get_i64("avgObjSize").unwrap(), 10);
+	assert_eq!(coll_stats.get_i64("indexCount").unwrap(), 1);
+	assert_eq!(coll_stats.get_document("indexSizes").unwrap(), &doc! { "_id_": 8192i64 });
+	assert!(coll_stats.get_bool("capped").unwrap());
+
+	let empty = extract_coll_stats("empty", &[]);
+	assert_eq!(empty.get_i64("avgObjSize").unwrap(), 0);
+	assert_eq!(empty.get_i64("indexCount").unwrap(), 0);
+}
@@ -0,0 +1,58 @@
+use crate::core::tls::create_client;
+use crate::core::uri::parse_uri;
+use mongodb::options::ClientOptions;
+
+#[tokio::test]
+async fn test_create_client_without_certificate() {
+	let client_options = ClientOptions::parse("mongodb://localhost:27017").await.unwrap();
+	assert!(create_client(client_options, "", "").is_ok());
+}
+
+#[tokio::test]
+async fn test_certificate_requires_tls() {
+	let client_options = ClientOptions::parse("mongodb://localhost:27017").await.unwrap();
+	let err = create_client(client_options, "-----BEGIN CERTIFICATE-----", "").unwrap_err();
+	assert!(err.to_string().contains("requires TLS"));
+}
+
+#[tokio::test]
+async fn test_invalid_certificate() {
+	let client_options = ClientOptions::parse("mongodb://localhost:27017/?tls=true").await.unwrap();
+	assert!(create_client(client_options, "not a certificate", "").is_err());
+}
+
+#[tokio::test]
+async fn test_ca_certificate() {
+	// CA 证书同样需要开启 TLS
+	let client_options = ClientOptions::parse("mongodb://localhost:27017").await.unwrap();
+	let err = create_client(client_options, "", "-----BEGIN CERTIFICATE-----").unwrap_err();
+	assert!(err.to_string().contains("requires TLS"));
+
+	let client_options = ClientOptions::parse("mongodb://localhost:27017/?tls=true").await.unwrap();
+	let err = create_client(client_options, "", "not a certificate").unwrap_err();
+	assert!(err.to_string().contains("PEM"));
+}
+
+#[cfg(feature = "openssl-tls")]
+#[tokio::test]
+async fn test_allow_invalid_hostnames() {
+	use mongodb::options::Tls;
+	let profile = parse_uri("mongodb://db1/?tls=true&tlsAllowInvalidHostnames=true").unwrap();
+	match profile.client_options().await.unwrap().tls {
+		Some(Tls::Enabled(tls_options)) => assert_eq!(tls_options.allow_invalid_hostnames, Some(true)),
+		_ => panic!("TLS should be enabled"),
+	}
+	let profile = parse_uri("mongodb://db1/?tlsAllowInvalidHostnames=true").unwrap();
+	assert!(profile.client_options().await.is_err());
+}
+
+// 默认的 rustls 后端不支持忽略主机名校验
+#[cfg(not(feature = "openssl-tls"))]
+#[tokio::test]
+async fn test_allow_invalid_hostnames() {
+	let profile = parse_uri("mongodb://db1/?tls=true&tlsAllowInvalidHostnames=true").unwrap();
+	let err = profile.client_options().await.unwrap_err();
+	assert!(err.to_string().contains("not supported"));
+	let profile = parse_uri("mongodb://db1/?tls=true").unwrap();
+	assert!(profile.client_options().await.is_ok());
+}
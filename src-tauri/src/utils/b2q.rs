@@ -0,0 +1,138 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use mongodb::bson::{spec::BinarySubtype, Bson, Document};
+
+/// 将 BSON 值格式化为 mongosh 风格的字符串
+/// - 输出可以被 `q2b::query_to_document` 重新解析，类型不丢失
+pub fn to_shell_string(value: &Bson) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+/// 将文档格式化为 mongosh 风格的字符串
+pub fn document_to_shell_string(doc: &Document) -> String {
+    let mut out = String::new();
+    write_document(&mut out, doc);
+    out
+}
+
+fn write_document(out: &mut String, doc: &Document) {
+    if doc.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+    out.push_str("{ ");
+    for (i, (key, value)) in doc.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        if is_plain_key(key) {
+            out.push_str(key);
+        } else {
+            write_string(out, key);
+        }
+        out.push_str(": ");
+        write_value(out, value);
+    }
+    out.push_str(" }");
+}
+
+fn write_value(out: &mut String, value: &Bson) {
+    match value {
+        Bson::Document(doc) => write_document(out, doc),
+        Bson::Array(items) => {
+            if items.is_empty() {
+                out.push_str("[]");
+                return;
+            }
+            out.push_str("[ ");
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, item);
+            }
+            out.push_str(" ]");
+        }
+        Bson::String(s) | Bson::Symbol(s) => write_string(out, s),
+        Bson::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        Bson::Null => out.push_str("null"),
+        Bson::Undefined => out.push_str("undefined"),
+        Bson::Int32(n) => out.push_str(&n.to_string()),
+        Bson::Int64(n) => out.push_str(&format!("NumberLong(\"{}\")", n)),
+        Bson::Double(n) => out.push_str(&format_double(*n)),
+        Bson::Decimal128(d) => out.push_str(&format!("NumberDecimal(\"{}\")", d)),
+        Bson::ObjectId(oid) => out.push_str(&format!("ObjectId(\"{}\")", oid.to_hex())),
+        Bson::DateTime(dt) => match dt.try_to_rfc3339_string() {
+            Ok(s) => out.push_str(&format!("ISODate(\"{}\")", s)),
+            // 超出 RFC3339 表示范围的日期使用毫秒数
+            Err(_) => out.push_str(&format!("new Date({})", dt.timestamp_millis())),
+        },
+        Bson::Timestamp(ts) => out.push_str(&format!("Timestamp({}, {})", ts.time, ts.increment)),
+        Bson::Binary(bin) if bin.subtype == BinarySubtype::Uuid && bin.bytes.len() == 16 => {
+            let h = hex::encode(&bin.bytes);
+            out.push_str(&format!(
+                "UUID(\"{}-{}-{}-{}-{}\")",
+                &h[0..8],
+                &h[8..12],
+                &h[12..16],
+                &h[16..20],
+                &h[20..32]
+            ));
+        }
+        Bson::Binary(bin) => out.push_str(&format!(
+            "BinData({}, \"{}\")",
+            u8::from(bin.subtype),
+            STANDARD.encode(&bin.bytes)
+        )),
+        Bson::RegularExpression(re) => {
+            if re.pattern.contains('/') || re.pattern.contains('\n') {
+                out.push_str("RegExp(");
+                write_string(out, &re.pattern);
+                out.push_str(", ");
+                write_string(out, &re.options);
+                out.push(')');
+            } else {
+                out.push_str(&format!("/{}/{}", re.pattern, re.options));
+            }
+        }
+        Bson::JavaScriptCode(code) => {
+            out.push_str("Code(");
+            write_string(out, code);
+            out.push(')');
+        }
+        Bson::JavaScriptCodeWithScope(code) => {
+            out.push_str("Code(");
+            write_string(out, &code.code);
+            out.push_str(", ");
+            write_document(out, &code.scope);
+            out.push(')');
+        }
+        Bson::MinKey => out.push_str("MinKey"),
+        Bson::MaxKey => out.push_str("MaxKey"),
+        // 已废弃的 DBPointer 没有 shell 字面量，使用扩展 JSON 表示
+        other => out.push_str(&other.clone().into_canonical_extjson().to_string()),
+    }
+}
+
+// 浮点数总是带小数点或指数，以便与 Int32 / Int64 区分
+fn format_double(n: f64) -> String {
+    if n.is_nan() {
+        "NaN".to_string()
+    } else if n.is_infinite() {
+        if n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+    } else {
+        format!("{:?}", n)
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    // serde_json 的转义规则是解析器可接受的子集
+    out.push_str(&serde_json::to_string(s).unwrap_or_default());
+}
+
+fn is_plain_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
@@ -76,6 +76,20 @@ pub fn get_value(
 
 /// 从指定文件中删除指定键的值。
 pub fn delete_value(skey: &str, app_handle: AppHandle, filename: &str) -> anyhow::Result<()> {
+    remove_values(app_handle, filename, |key| key == skey)
+}
+
+/// 从指定文件中删除所有以指定前缀开头的键的值。
+pub fn delete_prefixed_values(prefix: &str, app_handle: AppHandle, filename: &str) -> anyhow::Result<()> {
+    remove_values(app_handle, filename, |key| key.starts_with(prefix))
+}
+
+// 删除满足条件的键
+fn remove_values(
+    app_handle: AppHandle,
+    filename: &str,
+    matches: impl Fn(&str) -> bool,
+) -> anyhow::Result<()> {
     let data_path = app_handle
         .path()
         .app_data_dir()
@@ -87,7 +101,7 @@ pub fn delete_value(skey: &str, app_handle: AppHandle, filename: &str) -> anyhow
 
     //如果是 Object 类型，删除字段
     if let Value::Object(ref mut map) = data {
-        map.remove(skey);
+        map.retain(|key, _| !matches(key));
     }
 
     fs::write(data_path, data.to_string()).context("Failed to write data")?;
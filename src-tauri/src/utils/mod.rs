@@ -1,3 +1,5 @@
+pub mod b2q;
 pub mod crypt;
 pub mod io_op;
+pub mod parser;
 pub mod q2b;
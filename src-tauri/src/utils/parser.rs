@@ -0,0 +1,525 @@
+use super::q2b::{bson_regex, construct};
+use mongodb::bson::{Bson, Document};
+use std::fmt;
+
+// 解析错误，行列号均从 1 开始
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.reason)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 将 JSON5 + mongosh 语法的文本直接解析为 `Document`
+/// - 键可以不加引号，字符串可以使用单引号，允许注释和尾随逗号
+/// - `ObjectId(...)`、`new Date(...)`、`/re/i`、`MinKey` 等字面量直接转换为对应的 BSON 类型
+/// - 字符串内容永远不会被当作类型构造器
+pub fn parse_document(input: &str) -> Result<Document, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.skip_whitespace()?;
+    let (line, column) = (parser.line, parser.column);
+    match parser.parse_value()? {
+        Bson::Document(doc) => {
+            parser.expect_end()?;
+            Ok(doc)
+        }
+        other => Err(ParseError {
+            line,
+            column,
+            reason: format!("expected a document, found {}", type_name(&other)),
+        }),
+    }
+}
+
+/// 将文本解析为任意 BSON 值（如聚合管道数组）
+pub fn parse_value(input: &str) -> Result<Bson, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.skip_whitespace()?;
+    let value = parser.parse_value()?;
+    parser.expect_end()?;
+    Ok(value)
+}
+
+/// 解析结果及其在文本中的位置
+pub struct LocatedValue {
+    pub value: Bson,
+    pub line: usize,
+    pub column: usize,
+    /// 顶层为数组时各元素的起始位置，其他类型为空
+    pub items: Vec<(usize, usize)>,
+}
+
+/// 将文本解析为任意 BSON 值，同时记录值和顶层数组元素的起始位置
+/// - 调用方检查值的类型时，可以据此报告出错元素的行列号
+pub fn parse_located_value(input: &str) -> Result<LocatedValue, ParseError> {
+    let mut parser = Parser::new(input);
+    parser.skip_whitespace()?;
+    let (line, column) = (parser.line, parser.column);
+    let value = parser.parse_value()?;
+    parser.expect_end()?;
+    Ok(LocatedValue {
+        value,
+        line,
+        column,
+        items: parser.items,
+    })
+}
+
+// 用于错误提示的类型名称
+fn type_name(value: &Bson) -> &'static str {
+    match value {
+        Bson::Array(_) => "an array",
+        Bson::String(_) => "a string",
+        Bson::Document(_) => "a document",
+        Bson::Null => "null",
+        Bson::Boolean(_) => "a boolean",
+        Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) | Bson::Decimal128(_) => "a number",
+        _ => "a literal",
+    }
+}
+
+// 最大嵌套层数，与 MongoDB 文档的嵌套限制一致，避免过深的输入导致栈溢出
+const MAX_DEPTH: usize = 100;
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+    // 当前嵌套层数
+    depth: usize,
+    // 顶层数组各元素的起始位置
+    items: Vec<(usize, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            chars: input.chars().peekable(),
+            line: 1,
+            column: 1,
+            depth: 0,
+            items: Vec::new(),
+        }
+    }
+
+    fn error_at<T>(&self, line: usize, column: usize, reason: impl Into<String>) -> Result<T, ParseError> {
+        Err(ParseError {
+            line,
+            column,
+            reason: reason.into(),
+        })
+    }
+
+    fn error<T>(&self, reason: impl Into<String>) -> Result<T, ParseError> {
+        self.error_at(self.line, self.column, reason)
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => self.error(format!("expected '{}', found '{}'", expected, c)),
+            None => self.error(format!("expected '{}', found end of input", expected)),
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        self.skip_whitespace()?;
+        match self.peek() {
+            None => Ok(()),
+            Some(c) => self.error(format!("unexpected '{}' after the end of the value", c)),
+        }
+    }
+
+    // 跳过空白和 // 、/* */ 注释
+    fn skip_whitespace(&mut self) -> Result<(), ParseError> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') => {
+                    let mut lookahead = self.chars.clone();
+                    lookahead.next();
+                    match lookahead.next() {
+                        Some('/') => {
+                            while !matches!(self.peek(), None | Some('\n')) {
+                                self.bump();
+                            }
+                        }
+                        Some('*') => {
+                            let (line, column) = (self.line, self.column);
+                            self.bump();
+                            self.bump();
+                            loop {
+                                match self.bump() {
+                                    Some('*') if self.peek() == Some('/') => {
+                                        self.bump();
+                                        break;
+                                    }
+                                    Some(_) => {}
+                                    None => return self.error_at(line, column, "unterminated comment"),
+                                }
+                            }
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Bson, ParseError> {
+        self.skip_whitespace()?;
+        if self.depth >= MAX_DEPTH {
+            return self.error(format!("nesting exceeds the maximum depth of {}", MAX_DEPTH));
+        }
+        self.depth += 1;
+        let value = self.parse_nested_value();
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_nested_value(&mut self) -> Result<Bson, ParseError> {
+        match self.peek() {
+            Some('{') => self.parse_object().map(Bson::Document),
+            Some('[') => self.parse_array(),
+            Some('"' | '\'') => self.parse_string().map(Bson::String),
+            Some('/') => self.parse_regex(),
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => self.parse_number(),
+            Some(c) if is_ident_start(c) => self.parse_identifier_value(),
+            Some(c) => self.error(format!("unexpected '{}'", c)),
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<Document, ParseError> {
+        self.expect('{')?;
+        let mut doc = Document::new();
+        loop {
+            self.skip_whitespace()?;
+            if self.peek() == Some('}') {
+                self.bump();
+                return Ok(doc);
+            }
+            let key = self.parse_key()?;
+            self.skip_whitespace()?;
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            doc.insert(key, value);
+            self.skip_whitespace()?;
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {}
+                Some(c) => return self.error(format!("expected ',' or '}}', found '{}'", c)),
+                None => return self.error("unterminated document, expected '}'"),
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some('"' | '\'') => self.parse_string(),
+            // 允许 a.b 这样不加引号的点路径，以及数字键
+            Some(c) if is_ident_start(c) || c.is_ascii_digit() => {
+                let mut key = String::new();
+                while let Some(c) = self.peek() {
+                    if is_ident_part(c) || c == '.' {
+                        key.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                Ok(key)
+            }
+            Some(c) => self.error(format!("expected a field name, found '{}'", c)),
+            None => self.error("unterminated document, expected '}'"),
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Bson, ParseError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace()?;
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Bson::Array(items));
+            }
+            if self.depth == 1 {
+                self.items.push((self.line, self.column));
+            }
+            items.push(self.parse_value()?);
+            self.skip_whitespace()?;
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {}
+                Some(c) => return self.error(format!("expected ',' or ']', found '{}'", c)),
+                None => return self.error("unterminated array, expected ']'"),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let quote = self.bump().unwrap_or('"');
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                Some(c) if c == quote => return Ok(s),
+                Some('\\') => {
+                    let (esc_line, esc_column) = (self.line, self.column - 1);
+                    match self.bump() {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some('r') => s.push('\r'),
+                        Some('b') => s.push('\u{8}'),
+                        Some('f') => s.push('\u{c}'),
+                        Some('v') => s.push('\u{b}'),
+                        Some('0') => s.push('\0'),
+                        // 行尾的反斜杠表示续行
+                        Some('\n') => {}
+                        Some('x') => {
+                            let code = self.parse_hex_digits(2, esc_line, esc_column)?;
+                            s.push(char::from(code as u8));
+                        }
+                        Some('u') => {
+                            let high = self.parse_hex_digits(4, esc_line, esc_column)?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                // 代理对
+                                if self.bump() != Some('\\') || self.bump() != Some('u') {
+                                    return self.error_at(esc_line, esc_column, "invalid unicode surrogate pair");
+                                }
+                                let low = self.parse_hex_digits(4, esc_line, esc_column)?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return self.error_at(esc_line, esc_column, "invalid unicode surrogate pair");
+                                }
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                high
+                            };
+                            match char::from_u32(code) {
+                                Some(c) => s.push(c),
+                                None => return self.error_at(esc_line, esc_column, "invalid unicode escape"),
+                            }
+                        }
+                        Some(c) => s.push(c),
+                        None => return self.error_at(line, column, "unterminated string"),
+                    }
+                }
+                Some('\n') | None => return self.error_at(line, column, "unterminated string"),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn parse_hex_digits(&mut self, count: usize, line: usize, column: usize) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..count {
+            match self.bump().and_then(|c| c.to_digit(16)) {
+                Some(d) => code = code * 16 + d,
+                None => return self.error_at(line, column, "invalid hex escape"),
+            }
+        }
+        Ok(code)
+    }
+
+    fn parse_number(&mut self) -> Result<Bson, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+        if let Some(sign @ ('-' | '+')) = self.peek() {
+            text.push(sign);
+            self.bump();
+        }
+        if self.peek().is_some_and(is_ident_start) {
+            let ident = self.parse_identifier();
+            let negative = text == "-";
+            return match ident.as_str() {
+                "Infinity" if negative => Ok(Bson::Double(f64::NEG_INFINITY)),
+                "Infinity" => Ok(Bson::Double(f64::INFINITY)),
+                "NaN" => Ok(Bson::Double(f64::NAN)),
+                _ => self.error_at(line, column, format!("invalid number '{}{}'", text, ident)),
+            };
+        }
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_') || (matches!(c, '+' | '-') && text.ends_with(['e', 'E'])) {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let digits = text.trim_start_matches(['+', '-']);
+        let negative = text.starts_with('-');
+        if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+            return match i64::from_str_radix(hex, 16) {
+                Ok(n) => Ok(int_value(if negative { -n } else { n })),
+                Err(_) => self.error_at(line, column, format!("invalid number '{}'", text)),
+            };
+        }
+        if !digits.contains(['.', 'e', 'E']) {
+            if let Ok(n) = text.parse::<i64>() {
+                return Ok(int_value(n));
+            }
+        }
+        match text.parse::<f64>() {
+            Ok(n) if !digits.is_empty() && digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') => Ok(Bson::Double(n)),
+            _ => self.error_at(line, column, format!("invalid number '{}'", text)),
+        }
+    }
+
+    // /pattern/flags 正则字面量
+    fn parse_regex(&mut self) -> Result<Bson, ParseError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        let mut pattern = String::new();
+        let mut in_class = false;
+        loop {
+            match self.bump() {
+                Some('\\') => {
+                    pattern.push('\\');
+                    match self.bump() {
+                        Some('\n') | None => return self.error_at(line, column, "unterminated regular expression"),
+                        Some(c) => pattern.push(c),
+                    }
+                }
+                Some('[') => {
+                    in_class = true;
+                    pattern.push('[');
+                }
+                Some(']') => {
+                    in_class = false;
+                    pattern.push(']');
+                }
+                Some('/') if !in_class => break,
+                Some('\n') | None => return self.error_at(line, column, "unterminated regular expression"),
+                Some(c) => pattern.push(c),
+            }
+        }
+        if pattern.is_empty() {
+            return self.error_at(line, column, "empty regular expression");
+        }
+        let mut flags = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphabetic() {
+                if !matches!(c, 'i' | 'm' | 's' | 'x' | 'u' | 'l') {
+                    return self.error(format!("invalid regular expression flag '{}'", c));
+                }
+                flags.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        Ok(Bson::RegularExpression(bson_regex(&pattern, &flags)))
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if is_ident_part(c) {
+                ident.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        ident
+    }
+
+    // 关键字或类型构造器，如 true、MinKey、new Date(...)、ObjectId("...")
+    fn parse_identifier_value(&mut self) -> Result<Bson, ParseError> {
+        let (line, column) = (self.line, self.column);
+        let mut ident = self.parse_identifier();
+        let mut is_new = false;
+        if ident == "new" {
+            self.skip_whitespace()?;
+            if !self.peek().is_some_and(is_ident_start) {
+                return self.error("expected a constructor after 'new'");
+            }
+            ident = self.parse_identifier();
+            is_new = true;
+        }
+        self.skip_whitespace()?;
+        if self.peek() != Some('(') {
+            return match ident.as_str() {
+                _ if is_new => self.error(format!("expected '(' after 'new {}'", ident)),
+                "true" => Ok(Bson::Boolean(true)),
+                "false" => Ok(Bson::Boolean(false)),
+                "null" => Ok(Bson::Null),
+                "undefined" => Ok(Bson::Undefined),
+                "Infinity" => Ok(Bson::Double(f64::INFINITY)),
+                "NaN" => Ok(Bson::Double(f64::NAN)),
+                "MinKey" => Ok(Bson::MinKey),
+                "MaxKey" => Ok(Bson::MaxKey),
+                _ => self.error_at(line, column, format!("unexpected identifier '{}'", ident)),
+            };
+        }
+        self.bump();
+        let mut args = Vec::new();
+        loop {
+            self.skip_whitespace()?;
+            if self.peek() == Some(')') {
+                self.bump();
+                break;
+            }
+            args.push(self.parse_value()?);
+            self.skip_whitespace()?;
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(')') => {}
+                Some(c) => return self.error(format!("expected ',' or ')', found '{}'", c)),
+                None => return self.error(format!("unterminated call to {}, expected ')'", ident)),
+            }
+        }
+        construct(&ident, args).or_else(|reason| self.error_at(line, column, format!("{}: {}", ident, reason)))
+    }
+}
+
+fn int_value(n: i64) -> Bson {
+    match i32::try_from(n) {
+        Ok(n) => Bson::Int32(n),
+        Err(_) => Bson::Int64(n),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_ident_part(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
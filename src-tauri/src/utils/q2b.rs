@@ -1,89 +1,277 @@
-use log::error;
-use mongodb::bson::{oid::ObjectId, Bson, DateTime, Decimal128, Document};
-use regex::Regex;
-use std::sync::LazyLock;
+use super::parser::{parse_document, parse_located_value, ParseError};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use mongodb::bson::{
+    doc, oid::ObjectId, spec::BinarySubtype, Binary, Bson, DateTime, Decimal128, Document,
+    JavaScriptCodeWithScope, Regex as BsonRegex, Timestamp,
+};
+use mongodb::options::{Hint, UpdateModifications};
 
-pub static MONGO_SPECIAL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(
-        r##"(?x)
-        (ISODate|ObjectId|NumberDecimal)  # 方法名
-        \(                                                              # 左括号
-        ['"]                                                            # 引号开始
-        (?P<content>[^'"]+)                                             # 内容
-        ['"]                                                            # 引号结束
-        \)                                                              # 右括号
-        "##,
-    )
-    .unwrap()
-});
+/// 将查询语句解析为 `Document`，空语句视为匹配全部
+pub fn query_to_document(query: &str) -> Result<Document, ParseError> {
+    if query.trim().is_empty() {
+        return Ok(doc! {});
+    }
+    parse_document(query)
+}
+
+/// 解析投影、排序等可选的文档参数，未传或空字符串视为未指定
+pub fn optional_document(text: Option<&str>) -> Result<Option<Document>, ParseError> {
+    match text {
+        Some(text) if !text.trim().is_empty() => parse_document(text).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// 解析聚合管道，必须是由文档组成的数组
+pub fn parse_pipeline(text: &str) -> Result<Vec<Document>, ParseError> {
+    parse_document_list(text, "pipeline stage")
+}
+
+/// 解析批量插入的文档数组，单个文档可以省略外层数组
+pub fn parse_documents(text: &str) -> Result<Vec<Document>, ParseError> {
+    parse_document_list(text, "element")
+}
+
+fn parse_document_list(text: &str, item: &str) -> Result<Vec<Document>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let located = parse_located_value(text)?;
+    match located.value {
+        Bson::Array(items) => document_list(items, &located.items, item),
+        // 单个文档可以省略外层数组
+        Bson::Document(doc) => Ok(vec![doc]),
+        _ => Err(ParseError {
+            line: located.line,
+            column: located.column,
+            reason: "expected an array of documents".to_string(),
+        }),
+    }
+}
 
-// 转换文档中的所有值
-pub fn convert_document(doc: Document) -> Document {
-    doc.into_iter()
-        .map(|(k, v)| (k, convert_value(v)))
+// 检查数组的每个元素都是文档，出错时报告该元素的位置
+fn document_list(
+    items: Vec<Bson>,
+    positions: &[(usize, usize)],
+    item: &str,
+) -> Result<Vec<Document>, ParseError> {
+    items
+        .into_iter()
+        .zip(positions)
+        .enumerate()
+        .map(|(i, (value, &(line, column)))| match value {
+            Bson::Document(doc) => Ok(doc),
+            _ => Err(ParseError {
+                line,
+                column,
+                reason: format!("{} {} is not a document", item, i),
+            }),
+        })
         .collect()
 }
 
-// 转换单个值，处理ISODate字符串
-pub fn convert_value(value: Bson) -> Bson {
-    match value {
-        // 如果是嵌套文档，递归处理
-        Bson::Document(doc) => Bson::Document(convert_document(doc)),
-        // 如果是数组，逐个处理元素
-        Bson::Array(arr) => Bson::Array(arr.into_iter().map(convert_value).collect()),
-        // 如果是字符串，检查是否为支持格式
-        Bson::String(s) => {
-            if let Some(date_str) = parse_special_wrapper(&s, "ISODate") {
-                match DateTime::parse_rfc3339_str(date_str) {
-                    Ok(dt) => Bson::DateTime(dt),
-                    Err(e) => {
-                        error!("Failed to parse date '{}': {}", date_str, e);
-                        Bson::String(s)
-                    }
-                }
-            } else if let Some(oid_str) = parse_special_wrapper(&s, "ObjectId") {
-                match ObjectId::parse_str(oid_str) {
-                    Ok(oid) => Bson::ObjectId(oid),
-                    Err(e) => {
-                        error!("Failed to parse ObjectId '{}': {}", oid_str, e);
-                        Bson::String(s)
-                    }
-                }
-            } else if let Some(nd_str) = parse_special_wrapper(&s, "NumberDecimal") {
-                match nd_str.parse::<Decimal128>() {
-                    Ok(nd) => Bson::Decimal128(nd),
-                    Err(e) => {
-                        error!("Failed to parse Decimal128 '{}': {}", nd_str, e);
-                        Bson::String(s)
-                    }
-                }
+/// 解析更新内容，可以是更新操作符文档，也可以是聚合管道数组
+pub fn parse_update(text: &str) -> Result<UpdateModifications, ParseError> {
+    let located = parse_located_value(text)?;
+    match located.value {
+        Bson::Document(doc) => Ok(UpdateModifications::Document(doc)),
+        Bson::Array(stages) => {
+            document_list(stages, &located.items, "pipeline stage").map(UpdateModifications::Pipeline)
+        }
+        _ => Err(ParseError {
+            line: located.line,
+            column: located.column,
+            reason: "expected an update document or pipeline".to_string(),
+        }),
+    }
+}
+
+/// 解析 hint，可以是索引键文档 `{ a: 1 }`，也可以是索引名称（带不带引号均可）
+pub fn parse_hint(text: Option<&str>) -> Result<Option<Hint>, ParseError> {
+    let text = match text {
+        Some(text) if !text.trim().is_empty() => text,
+        _ => return Ok(None),
+    };
+    if !text.trim().starts_with(['{', '"', '\'']) {
+        return Ok(Some(Hint::Name(text.trim().to_string())));
+    }
+    let located = parse_located_value(text)?;
+    match located.value {
+        Bson::Document(keys) => Ok(Some(Hint::Keys(keys))),
+        Bson::String(name) => Ok(Some(Hint::Name(name))),
+        _ => Err(ParseError {
+            line: located.line,
+            column: located.column,
+            reason: "expected an index name or key document".to_string(),
+        }),
+    }
+}
+
+/// 根据 mongosh 中的构造器名称和参数生成对应的 BSON 值
+/// - 由解析器在遇到 `Name(...)` 或 `new Name(...)` 时调用
+/// - 返回的错误信息不含位置，由解析器补充行列号
+pub fn construct(name: &str, args: Vec<Bson>) -> Result<Bson, String> {
+    let mut args = args.into_iter();
+    let mut arg = || args.next();
+    let value = match name {
+        "ObjectId" => match arg() {
+            None => Bson::ObjectId(ObjectId::new()),
+            Some(Bson::String(s)) => Bson::ObjectId(ObjectId::parse_str(&s).map_err(|e| e.to_string())?),
+            Some(other) => return Err(format!("expected a hex string, found {}", other)),
+        },
+        "ISODate" | "Date" => match arg() {
+            None => Bson::DateTime(DateTime::now()),
+            Some(Bson::String(s)) => Bson::DateTime(parse_shell_date(&s)?),
+            Some(other) => Bson::DateTime(DateTime::from_millis(as_i64(&other)?)),
+        },
+        "NumberDecimal" | "Decimal128" => {
+            let s = number_text(arg())?;
+            Bson::Decimal128(s.parse::<Decimal128>().map_err(|e| e.to_string())?)
+        }
+        "NumberLong" | "Long" => {
+            let s = number_text(arg())?;
+            Bson::Int64(s.parse::<i64>().map_err(|e| e.to_string())?)
+        }
+        "NumberInt" | "Int32" => {
+            let s = number_text(arg())?;
+            Bson::Int32(s.parse::<i32>().map_err(|e| e.to_string())?)
+        }
+        "Double" => {
+            let s = number_text(arg())?;
+            Bson::Double(s.parse::<f64>().map_err(|e| e.to_string())?)
+        }
+        "UUID" => match arg() {
+            Some(Bson::String(s)) => parse_uuid(&s)?,
+            _ => return Err("expected a UUID string".to_string()),
+        },
+        "BinData" | "HexData" => {
+            let subtype = as_i64(&arg().ok_or("expected (subtype, data)")?)?;
+            let subtype = u8::try_from(subtype).map_err(|_| format!("invalid subtype {}", subtype))?;
+            let data = match arg() {
+                Some(Bson::String(s)) => s,
+                _ => return Err("expected (subtype, data)".to_string()),
+            };
+            let bytes = if name == "HexData" {
+                hex::decode(data.trim()).map_err(|e| e.to_string())?
             } else {
-                Bson::String(s)
+                STANDARD.decode(data.trim()).map_err(|e| e.to_string())?
+            };
+            Bson::Binary(Binary {
+                subtype: BinarySubtype::from(subtype),
+                bytes,
+            })
+        }
+        "Timestamp" => match (arg(), arg()) {
+            // mongosh 中的 Timestamp({ t: ..., i: ... })
+            (Some(Bson::Document(ts)), None) => Bson::Timestamp(Timestamp {
+                time: as_u32(ts.get("t").ok_or("missing field 't'")?)?,
+                increment: as_u32(ts.get("i").ok_or("missing field 'i'")?)?,
+            }),
+            (Some(time), Some(increment)) => Bson::Timestamp(Timestamp {
+                time: as_u32(&time)?,
+                increment: as_u32(&increment)?,
+            }),
+            _ => return Err("expected (time, increment)".to_string()),
+        },
+        "RegExp" | "BSONRegExp" => match (arg(), arg()) {
+            (Some(Bson::String(pattern)), None) => Bson::RegularExpression(bson_regex(&pattern, "")),
+            (Some(Bson::String(pattern)), Some(Bson::String(options))) => {
+                Bson::RegularExpression(bson_regex(&pattern, &options))
             }
+            (Some(Bson::RegularExpression(regex)), None) => Bson::RegularExpression(regex),
+            _ => return Err("expected (pattern, flags)".to_string()),
+        },
+        "Code" => match (arg(), arg()) {
+            (Some(Bson::String(code)), None) => Bson::JavaScriptCode(code),
+            (Some(Bson::String(code)), Some(Bson::Document(scope))) => {
+                Bson::JavaScriptCodeWithScope(JavaScriptCodeWithScope { code, scope })
+            }
+            _ => return Err("expected (code, scope)".to_string()),
+        },
+        "DBRef" => {
+            let collection = match arg() {
+                Some(Bson::String(s)) => s,
+                _ => return Err("expected (collection, id, db)".to_string()),
+            };
+            let id = arg().ok_or("expected (collection, id, db)")?;
+            let mut dbref = doc! { "$ref": collection, "$id": id };
+            match arg() {
+                Some(Bson::String(db)) => {
+                    dbref.insert("$db", db);
+                }
+                None => {}
+                Some(_) => return Err("expected a database name string".to_string()),
+            }
+            Bson::Document(dbref)
         }
-        // 其他类型保持不变 or _ => value,
-        other => other,
+        "MinKey" => Bson::MinKey,
+        "MaxKey" => Bson::MaxKey,
+        _ => return Err("unknown constructor".to_string()),
+    };
+    if arg().is_some() {
+        return Err("too many arguments".to_string());
     }
+    Ok(value)
 }
 
-// 解析预处理后的格式
-pub fn parse_special_wrapper<'a>(
-	s: &'a str, 
-	method_name: &str
-) -> Option<&'a str> {
-    let prefix = format!("{}(", method_name);
-    if s.starts_with(&prefix) && s.ends_with(')') {
-        // 提取方法名( 和 ) 之间的内容
-        let content = &s[prefix.len()..s.len() - 1];
-        Some(content)
-    } else {
-        None
+// 构造 BSON 正则，选项需要按字母顺序排列
+pub fn bson_regex(pattern: &str, options: &str) -> BsonRegex {
+    let mut options: Vec<char> = options.chars().collect();
+    options.sort_unstable();
+    BsonRegex {
+        pattern: pattern.to_string(),
+        options: options.into_iter().collect(),
+    }
+}
+
+// 数字类构造器的参数可以是字符串或数字
+fn number_text(arg: Option<Bson>) -> Result<String, String> {
+    match arg {
+        Some(Bson::String(s)) => Ok(s.trim().to_string()),
+        Some(Bson::Int32(n)) => Ok(n.to_string()),
+        Some(Bson::Int64(n)) => Ok(n.to_string()),
+        Some(Bson::Double(n)) => Ok(n.to_string()),
+        Some(other) => Err(format!("expected a number or string, found {}", other)),
+        None => Ok("0".to_string()),
+    }
+}
+
+fn as_i64(value: &Bson) -> Result<i64, String> {
+    match value {
+        Bson::Int32(n) => Ok(*n as i64),
+        Bson::Int64(n) => Ok(*n),
+        Bson::Double(n) if n.fract() == 0.0 => Ok(*n as i64),
+        other => Err(format!("expected an integer, found {}", other)),
+    }
+}
+
+fn as_u32(value: &Bson) -> Result<u32, String> {
+    let n = as_i64(value)?;
+    u32::try_from(n).map_err(|_| format!("{} is out of range", n))
+}
+
+// 解析 shell 中的日期，支持 RFC3339、仅日期以及缺省时区
+fn parse_shell_date(date_str: &str) -> Result<DateTime, String> {
+    let date_str = date_str.trim();
+    if let Ok(dt) = DateTime::parse_rfc3339_str(date_str) {
+        return Ok(dt);
     }
+    let normalized = if date_str.len() == 10 {
+        format!("{}T00:00:00Z", date_str)
+    } else {
+        format!("{}Z", date_str.replacen(' ', "T", 1))
+    };
+    DateTime::parse_rfc3339_str(&normalized).map_err(|e| e.to_string())
 }
 
-// 使用正则表达式进行预处理
-pub fn preprocess_query(query: &str) -> String {
-    MONGO_SPECIAL_REGEX
-        .replace_all(query, r#""$1($content)""#)
-        .to_string()
+// UUID("xxxxxxxx-xxxx-...") 转换为 subtype 4 的 Binary
+fn parse_uuid(uuid_str: &str) -> Result<Bson, String> {
+    let bytes = hex::decode(uuid_str.trim().replace('-', "")).map_err(|e| e.to_string())?;
+    if bytes.len() != 16 {
+        return Err(format!("UUID must be 16 bytes, got {}", bytes.len()));
+    }
+    Ok(Bson::Binary(Binary {
+        subtype: BinarySubtype::Uuid,
+        bytes,
+    }))
 }
@@ -22,13 +22,40 @@ export default {
 		authMethods: "MongoDb Authentication",
 		noAuth: "No Authentication",
 		authNeeded: "Username/Password",
+		authSource: "Auth Database (authSource)",
+		authSourcePlaceholder: "Defaults to the connection database",
+		authMechanismProperties: "Auth Mechanism Properties",
 		mongoUserName: "MongoDb Username",
 		mongoPassWord: "MongoDb Password",
 		sshHost: "SSH Host",
 		sshPort: "SSH Port",
 		sshUserName: "ssh Username",
 		sshPassWord: "SSH Password",
+		sshAuthMethod: "SSH Authentication Method",
+		sshPrivateKey: "SSH Private Key (OpenSSH/PEM)",
+		sshPassphrase: "Private Key Passphrase (optional)",
+		sshPassWordOptional: "SSH Password (optional)",
+		keyboardInteractive: "Keyboard-Interactive / OTP",
+		sshJumpHosts: "Jump Hosts",
+		sshJumpHostsHint: "Hops after the SSH host, connected in order; the last one reaches MongoDB.",
+		sshAddJumpHost: "Add Jump Host",
+		sshJumpHost: "Jump Host {n}",
 		databaseName: "Database Name",
+		readOnly: "Read-only",
+		importUri: "Paste a connection string to import",
+		exportUri: "Connection String",
+		exportUriMsg: "The password is not included.",
+		advanced: "Advanced",
+		hosts: "Hosts (comma separated)",
+		replicaSet: "Replica Set",
+		readPreference: "Read Preference",
+		directConnection: "Direct Connection",
+		tls: "TLS/SSL",
+		tlsCaCertificate: "CA Certificate (PEM)",
+		tlsCaCertificateHint: "Paste the CA certificate that signed the server certificate; it is stored encrypted",
+		tlsCertificateKey: "Client Certificate and Key (PEM)",
+		tlsCertificateKeyHint: "Paste the certificate followed by its private key; it is stored encrypted",
+		tlsAllowInvalidHostnames: "Allow Invalid Hostnames",
 		reset: "Reset",
 		save: "Save",
 		deleteServer: "Delete Server Connection",
@@ -44,11 +71,14 @@ export default {
 			portRules: ["Port number cannot be empty", "Port number must be between 1 and 65535"],
 			userNameRules: "Username cannot be empty",
 			passWordRules: "Password cannot be empty",
-			dbNameRules: "Database name cannot be empty"
+			privateKeyRules: "Private key cannot be empty",
+			dbNameRules: "Database name cannot be empty",
+			certificateRules: "Paste the saved certificate again to keep it"
 		},
 		KeyCheckFailed: "The key verification failed, which is inconsistent with the first trust key. It may be a man-in-the-middle attack or a server key update. Please delete this connection record and add it again.",
 		FirstKeyCheck: {
 			msg: "Connect for the first time, verify the server key, do you trust this connection?",
+			host: "Host:",
 			alg: "Algorithm:",
 			fp: "Fingerprint:"
 		}
@@ -58,6 +88,11 @@ export default {
 		storageSize: "Storage",
 		docCount: "Doc",
 		indexCount: "Index",
+		view: "View of",
+		timeseries: "Time series",
+		statsError: "Stats unavailable",
+		switchDatabase: "Switch database",
+		emptyDatabase: "Empty",
 		queryPlaceholder: "Type a query",
 		loadingData: "Loading data...",
 		noAnyDocs: "No documents found",
@@ -22,13 +22,40 @@ export default {
 		authMethods: "mongodb 认证",
 		noAuth: "无需认证",
 		authNeeded: "用户名/密码",
+		authSource: "认证数据库（authSource）",
+		authSourcePlaceholder: "默认为连接的数据库",
+		authMechanismProperties: "认证机制属性",
 		mongoUserName: "mongodb用户名",
 		mongoPassWord: "mongodb密码",
 		sshHost: "ssh服务器地址",
 		sshPort: "ssh服务器端口",
 		sshUserName: "ssh用户名",
 		sshPassWord: "ssh服务器密码",
+		sshAuthMethod: "ssh认证方式",
+		sshPrivateKey: "ssh私钥（OpenSSH/PEM）",
+		sshPassphrase: "私钥口令（可选）",
+		sshPassWordOptional: "ssh服务器密码（可选）",
+		keyboardInteractive: "键盘交互 / 动态验证码",
+		sshJumpHosts: "跳板机",
+		sshJumpHostsHint: "在ssh服务器之后依次连接，由最后一台访问mongodb。",
+		sshAddJumpHost: "添加跳板机",
+		sshJumpHost: "跳板机 {n}",
 		databaseName: "数据库名称",
+		readOnly: "只读连接",
+		importUri: "粘贴连接串导入",
+		exportUri: "连接串",
+		exportUriMsg: "连接串中不包含密码。",
+		advanced: "高级选项",
+		hosts: "节点列表（逗号分隔）",
+		replicaSet: "副本集名称",
+		readPreference: "读偏好",
+		directConnection: "直接连接",
+		tls: "TLS/SSL",
+		tlsCaCertificate: "CA 证书（PEM）",
+		tlsCaCertificateHint: "粘贴签发服务器证书的 CA 证书，加密保存",
+		tlsCertificateKey: "客户端证书和私钥（PEM）",
+		tlsCertificateKeyHint: "依次粘贴证书和私钥，加密保存",
+		tlsAllowInvalidHostnames: "允许主机名不匹配",
 		reset: "重置",
 		save: "保存",
 		deleteServer: "删除该服务连接",
@@ -44,11 +71,14 @@ export default {
 			portRules: ["端口号不能为空", "端口号必须在1-65535之间"],
 			userNameRules: "用户名不能为空",
 			passWordRules: "密码不能为空",
-			dbNameRules: "数据库名称不能为空"
+			privateKeyRules: "私钥不能为空",
+			dbNameRules: "数据库名称不能为空",
+			certificateRules: "请重新粘贴已保存的证书"
 		},
 		KeyCheckFailed: "密钥验证失败，与第一次信任密钥不一致，可能是中间人攻击或者服务器密钥更新。请删除此连接记录重新添加。",
 		FirstKeyCheck: {
 			msg: "首次连接，验证服务器密钥，是否信任此连接？",
+			host: "主机：",
 			alg: "算法：",
 			fp: "指纹："
 		}
@@ -58,6 +88,11 @@ export default {
 		storageSize: "存储大小",
 		docCount: "文档数量",
 		indexCount: "索引数量",
+		view: "视图",
+		timeseries: "时序集合",
+		statsError: "统计信息获取失败",
+		switchDatabase: "切换数据库",
+		emptyDatabase: "空数据库",
 		queryPlaceholder: "输入查询条件",
 		loadingData: "正在加载数据...",
 		noAnyDocs: "没有找到任何文档",